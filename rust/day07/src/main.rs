@@ -1,116 +1,374 @@
 use std::cmp::Ordering;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
 const FILEPATH: &str = "./input/input.txt";
 
-#[derive(Eq, PartialEq)]
+const NUMBER_WORDS: [&str; 11] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+];
+
+#[derive(Clone, PartialEq, Eq)]
+enum Pattern {
+    // group sizes, largest first, that the hand must be able to cover
+    Groups(Vec<usize>),
+    // every card distinct and consecutive in rank
+    Straight,
+}
+
+#[derive(Clone)]
+struct Category {
+    name: String,
+    pattern: Pattern,
+}
+
+impl Category {
+    fn groups(sizes: &[usize]) -> Self {
+        let mut sizes = sizes.to_vec();
+        sizes.sort_by(|a, b| b.cmp(a));
+        let name = Self::groups_name(&sizes);
+
+        Self {
+            name,
+            pattern: Pattern::Groups(sizes),
+        }
+    }
+
+    fn straight() -> Self {
+        Self {
+            name: String::from("straight"),
+            pattern: Pattern::Straight,
+        }
+    }
+
+    fn groups_name(sizes: &[usize]) -> String {
+        let word = |n: usize| {
+            NUMBER_WORDS
+                .get(n)
+                .map(|w| w.to_string())
+                .unwrap_or(n.to_string())
+        };
+
+        match sizes {
+            [] => String::from("high card"),
+            [3, 2] => String::from("full house"),
+            _ => {
+                let pairs = sizes.iter().filter(|&&s| s == 2).count();
+                let mut pcs = sizes
+                    .iter()
+                    .filter(|&&s| s > 2)
+                    .map(|&s| format!("{} of a kind", word(s)))
+                    .collect::<Vec<_>>();
+                if pairs > 0 {
+                    pcs.push(format!("{} pair", word(pairs)));
+                }
+                pcs.join(" + ")
+            }
+        }
+    }
+}
+
+struct Rules {
+    ranks: Vec<char>,
+    wildcards: Vec<char>,
+    hand_size: usize,
+    categories: Vec<Category>,
+}
+
+impl Rules {
+    fn new(ranks: &str, wildcards: &str, hand_size: usize) -> Self {
+        let ranks = ranks.chars().collect::<Vec<_>>();
+        let wildcards = wildcards.chars().collect::<Vec<_>>();
+
+        // every way of splitting the hand into groups of two or more, ordered so that
+        // a bigger largest group always wins (the usual camel cards ladder for five cards)
+        let mut patterns = vec![];
+        Self::partitions(hand_size, hand_size, &mut vec![], &mut patterns);
+        patterns.sort();
+
+        let categories = patterns.iter().map(|p| Category::groups(p)).collect();
+
+        Self {
+            ranks,
+            wildcards,
+            hand_size,
+            categories,
+        }
+    }
+
+    fn standard() -> Self {
+        Self::new("23456789TJQKA", "", 5)
+    }
+
+    fn with_jokers() -> Self {
+        Self::new("23456789TQKA", "J", 5)
+    }
+
+    fn partitions(
+        remaining: usize,
+        max_part: usize,
+        current: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        out.push(current.clone());
+        for part in 2..=max_part.min(remaining) {
+            current.push(part);
+            Self::partitions(remaining - part, part, current, out);
+            current.pop();
+        }
+    }
+
+    fn insert_category(&mut self, category: Category, above: &str) -> Result<(), String> {
+        let idx = self
+            .categories
+            .iter()
+            .position(|c| c.name == above)
+            .ok_or(format!("Unknown category '{}'", above))?;
+        self.categories.insert(idx + 1, category);
+
+        Ok(())
+    }
+
+    fn card_value(&self, card: char) -> Option<u32> {
+        if let Some(idx) = self.wildcards.iter().position(|&w| w == card) {
+            Some(idx as u32)
+        } else {
+            self.ranks
+                .iter()
+                .position(|&r| r == card)
+                .map(|idx| (self.wildcards.len() + idx) as u32)
+        }
+    }
+
+    // finds the strongest category the hand can reach along with the cards the
+    // wildcards stand in for to reach it
+    fn classify(&self, cards: &[char]) -> (u32, Vec<char>) {
+        let wild = cards.iter().filter(|c| self.wildcards.contains(c)).count();
+        let naturals = cards
+            .iter()
+            .filter_map(|c| self.ranks.iter().position(|r| r == c))
+            .collect::<Vec<_>>();
+
+        let mut counts = vec![0; self.ranks.len()];
+        for &r in naturals.iter() {
+            counts[r] += 1;
+        }
+        // (size, rank) of each natural group, biggest (then highest) first
+        let mut groups = counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c > 0)
+            .map(|(r, &c)| (c, r))
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        let (idx, fills) = self
+            .categories
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(idx, cat)| {
+                let fills = match &cat.pattern {
+                    Pattern::Groups(sizes) => self.fill_groups(sizes, &groups, wild),
+                    Pattern::Straight => self.fill_straight(&naturals, &groups, cards.len()),
+                }?;
+                Some((idx, fills))
+            })
+            .unwrap();
+
+        let mut fills = fills.into_iter();
+        let best = cards
+            .iter()
+            .map(|&c| {
+                if self.wildcards.contains(&c) {
+                    fills.next().map(|r| self.ranks[r]).unwrap_or(c)
+                } else {
+                    c
+                }
+            })
+            .collect::<Vec<_>>();
+
+        (idx as u32, best)
+    }
+
+    fn fill_groups(
+        &self,
+        sizes: &[usize],
+        groups: &[(usize, usize)],
+        wild: usize,
+    ) -> Option<Vec<usize>> {
+        // pairing the largest required group with the largest natural group
+        // (and so on) minimises the number of wildcards needed
+        let mut unused = (0..self.ranks.len())
+            .rev()
+            .filter(|r| groups.iter().all(|(_, g)| g != r));
+        let mut fills = vec![];
+        for (i, &size) in sizes.iter().enumerate() {
+            let (have, rank) = match groups.get(i) {
+                Some(&group) => group,
+                None => (0, unused.next()?),
+            };
+            fills.extend((have..size).map(|_| rank));
+        }
+        if fills.len() > wild {
+            return None;
+        }
+
+        let spare = groups
+            .first()
+            .map(|&(_, r)| r)
+            .unwrap_or(self.ranks.len() - 1);
+        fills.resize(wild, spare);
+
+        Some(fills)
+    }
+
+    fn fill_straight(
+        &self,
+        naturals: &[usize],
+        groups: &[(usize, usize)],
+        hand_size: usize,
+    ) -> Option<Vec<usize>> {
+        if self.ranks.len() < hand_size || groups.iter().any(|&(g, _)| g > 1) {
+            return None;
+        }
+        let top_start = self.ranks.len() - hand_size;
+        let start = match (naturals.iter().min(), naturals.iter().max()) {
+            (Some(&lo), Some(&hi)) if hi - lo >= hand_size => return None,
+            (Some(&lo), _) => lo.min(top_start),
+            _ => top_start,
+        };
+
+        Some(
+            (start..start + hand_size)
+                .filter(|r| !naturals.contains(r))
+                .collect(),
+        )
+    }
+}
+
 struct Hand {
+    cards: String,
+    category: String,
+    best: String,
     score: Vec<u32>,
     bid: u32,
 }
 
 impl Hand {
-    fn from_str(s: &str, joker: bool) -> Self {
-        let pcs = s.trim().split_whitespace().collect::<Vec<_>>();
-        let score = Self::score(&pcs[0], joker);
+    fn from_str(s: &str, rules: &Rules) -> Self {
+        let pcs = s.split_whitespace().collect::<Vec<_>>();
         let bid = pcs[1].parse::<u32>().unwrap();
 
-        Self { score, bid }
+        Self::new(pcs[0], bid, rules)
     }
 
-    fn score(s: &str, joker: bool) -> Vec<u32> {
-        let cards = s
-            .chars()
-            .map(|ch| Self::parse_card(ch, joker))
-            .collect::<Vec<_>>();
-
-        let counts = (0..15)
-            .map(|val| cards.iter().filter(|&&c| c == val).count() as u32)
-            .collect::<Vec<_>>();
-
-        let type_ = Self::get_hand_type(counts.clone());
+    fn new(s: &str, bid: u32, rules: &Rules) -> Self {
+        let cards = s.chars().collect::<Vec<_>>();
+        if cards.len() != rules.hand_size {
+            panic!(
+                "Hand {} has {} cards, expected {}",
+                s,
+                cards.len(),
+                rules.hand_size
+            );
+        }
 
+        let (type_, best) = rules.classify(&cards);
         let mut score = vec![type_];
-        score.extend(cards.clone());
+        score.extend(cards.iter().map(|&card| {
+            rules
+                .card_value(card)
+                .unwrap_or_else(|| panic!("Unknown card {} in hand {}", card, s))
+        }));
 
-        score
+        Self {
+            cards: String::from(s),
+            category: rules.categories[type_ as usize].name.clone(),
+            best: best.into_iter().collect(),
+            score,
+            bid,
+        }
     }
 
-    fn parse_card(card: char, joker: bool) -> u32 {
-        match card {
-            'A' => 14,
-            'K' => 13,
-            'Q' => 12,
-            'J' if joker => 0,
-            'J' => 11,
-            'T' => 10,
-            _ => card.to_digit(10).unwrap(),
+    fn substitution(&self) -> String {
+        if self.best == self.cards {
+            String::from("-")
+        } else {
+            format!("{} -> {}", self.cards, self.best)
         }
     }
 
-    fn get_hand_type(counts: Vec<u32>) -> u32 {
-        if counts[0] == 0 {
-            // no jokers
-            let count_counts = (0..=5)
-                .map(|c| counts.iter().filter(|&&c2| c2 == c).count())
-                .collect::<Vec<_>>();
+    fn explain(&self, other: &Self) -> String {
+        let verdict = |ord: Ordering| match ord {
+            Ordering::Greater => "beats",
+            Ordering::Less => "loses to",
+            Ordering::Equal => "ties with",
+        };
 
-            let type_ = if count_counts[5] == 1 {
-                6
-            } else if count_counts[4] == 1 {
-                5
-            } else if count_counts[3] == 1 {
-                3 + count_counts[2]
-            } else {
-                count_counts[2]
-            };
+        if self.score[0] != other.score[0] {
+            return format!(
+                "{} ({}) {} {} ({}) on category",
+                self.cards,
+                self.category,
+                verdict(self.score[0].cmp(&other.score[0])),
+                other.cards,
+                other.category
+            );
+        }
 
-            type_ as u32
-        } else {
-            // handle jokers
-            (2..=14)
-                .filter_map(|repl| {
-                    if repl == 11 {
-                        None
-                    } else {
-                        let new_counts = counts
-                            .iter()
-                            .enumerate()
-                            .map(|(i, &ct)| match i {
-                                0 => ct - 1,
-                                i if i == repl => ct + 1,
-                                _ => ct,
-                            })
-                            .collect::<Vec<_>>();
-                        Some(Self::get_hand_type(new_counts))
-                    }
-                })
-                .max()
-                .unwrap()
+        let first_diff = self
+            .cards
+            .chars()
+            .zip(other.cards.chars())
+            .zip(self.score[1..].iter().zip(other.score[1..].iter()))
+            .enumerate()
+            .find(|(_, (_, (a, b)))| a != b);
+
+        match first_diff {
+            Some((i, ((ch, other_ch), (a, b)))) => format!(
+                "{} {} {} (both {}); tie broken at card {} ({} vs {})",
+                self.cards,
+                verdict(a.cmp(b)),
+                other.cards,
+                self.category,
+                i + 1,
+                ch,
+                other_ch
+            ),
+            None => format!(
+                "{} ties with {} (both {}); every card is equal",
+                self.cards, other.cards, self.category
+            ),
         }
     }
 }
 
+// hands are ordered by category and then every card in turn. the bid and the
+// display fields play no part, so equality is defined over the score as well
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        (0..6)
-            .find_map(|i| match self.score[i].cmp(&other.score[i]) {
-                Ordering::Equal => None,
-                ord => Some(ord),
-            })
-            .unwrap_or(Ordering::Equal)
+        self.score.cmp(&other.score)
     }
 }
 
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for Hand {}
+
 impl PartialOrd for Hand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-fn get_winnings(hands: &Vec<Hand>) -> u32 {
-    let mut hands = hands.iter().map(|h| h).collect::<Vec<_>>();
+fn get_winnings(hands: &[Hand]) -> u32 {
+    let mut hands = hands.iter().collect::<Vec<_>>();
     hands.sort();
 
     hands
@@ -120,16 +378,110 @@ fn get_winnings(hands: &Vec<Hand>) -> u32 {
         .sum()
 }
 
-fn parse_input(raw_input: &str) -> (Vec<Hand>, Vec<Hand>) {
-    let mut standard = vec![];
-    let mut with_joker = vec![];
+fn print_report(hands: &[Hand]) {
+    let mut hands = hands.iter().collect::<Vec<_>>();
+    hands.sort();
+
+    println!(
+        "{:>5}  {:<8} {:<18} {:<20} {:>5} {:>10}",
+        "Rank", "Hand", "Category", "Substitution", "Bid", "Winnings"
+    );
+    for (i, hand) in hands.iter().enumerate() {
+        let rank = i as u32 + 1;
+        println!(
+            "{:>5}  {:<8} {:<18} {:<20} {:>5} {:>10}",
+            rank,
+            hand.cards,
+            hand.category,
+            hand.substitution(),
+            hand.bid,
+            rank * hand.bid
+        );
+        if i > 0 {
+            println!("{:>7}{}", "", hand.explain(hands[i - 1]));
+        }
+    }
+}
+
+fn parse_input(raw_input: &str, rules: &Rules) -> Vec<Hand> {
+    raw_input
+        .trim()
+        .lines()
+        .map(|ln| Hand::from_str(ln, rules))
+        .collect()
+}
+
+struct Options {
+    rules: Option<Rules>,
+    report: bool,
+    compare: Option<(String, String)>,
+}
 
-    for ln in raw_input.trim().lines() {
-        standard.push(Hand::from_str(ln, false));
-        with_joker.push(Hand::from_str(ln, true));
+// --ranks, --wild and --size describe a custom rule set; --extra "name=pattern@above"
+// adds a category (pattern is "straight" or group sizes such as "2,2,2,2,2").
+// --report lists every hand with its ranking and --compare explains one matchup
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut ranks = String::from("23456789TJQKA");
+    let mut wild = String::new();
+    let mut size = 5;
+    let mut extras = vec![];
+    let mut custom = false;
+    let mut report = false;
+    let mut compare = None;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", flag));
+        match flag.as_str() {
+            "--report" => report = true,
+            "--compare" => compare = Some((value()?.clone(), value()?.clone())),
+            "--ranks" => ranks = value()?.clone(),
+            "--wild" => wild = value()?.clone(),
+            "--size" => {
+                let val = value()?;
+                size = val.parse().map_err(|_| format!("Bad size {}", val))?
+            }
+            "--extra" => extras.push(value()?.clone()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+        custom |= !matches!(flag.as_str(), "--report" | "--compare");
     }
+    if !custom {
+        return Ok(Options {
+            rules: None,
+            report,
+            compare,
+        });
+    }
+    let ranks = ranks.replace(|c| wild.contains(c), "");
 
-    (standard, with_joker)
+    let mut rules = Rules::new(&ranks, &wild, size);
+    for extra in extras {
+        let (name, rest) = extra
+            .split_once('=')
+            .ok_or(format!("Bad category {}", extra))?;
+        let (pattern, above) = rest
+            .split_once('@')
+            .ok_or(format!("Bad category {}", extra))?;
+        let mut category = if pattern == "straight" {
+            Category::straight()
+        } else {
+            let sizes = pattern
+                .split(',')
+                .map(|s| s.trim().parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("Bad pattern {}", pattern))?;
+            Category::groups(&sizes)
+        };
+        category.name = String::from(name);
+        rules.insert_category(category, above)?;
+    }
+
+    Ok(Options {
+        rules: Some(rules),
+        report,
+        compare,
+    })
 }
 
 fn main() {
@@ -138,14 +490,211 @@ fn main() {
 
     let now = Instant::now();
 
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let options = parse_args(&args).unwrap_or_else(|err| panic!("{}", err));
+
+    if let Some((a, b)) = &options.compare {
+        let rules = options.rules.unwrap_or_else(Rules::with_jokers);
+        let (a, b) = (Hand::new(a, 0, &rules), Hand::new(b, 0, &rules));
+        println!("{}", a.explain(&b));
+        return;
+    }
+
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let (standard_hands, joker_hands) = parse_input(&raw_input);
 
-    let part_one = get_winnings(&standard_hands);
-    let part_two = get_winnings(&joker_hands);
+    if let Some(rules) = options.rules {
+        let hands = parse_input(&raw_input, &rules);
+        if options.report {
+            print_report(&hands);
+        }
+        println!("Winnings: {}", get_winnings(&hands));
+    } else {
+        let standard_hands = parse_input(&raw_input, &Rules::standard());
+        let joker_hands = parse_input(&raw_input, &Rules::with_jokers());
+
+        if options.report {
+            println!("Standard rules");
+            print_report(&standard_hands);
+            println!("Joker rules");
+            print_report(&joker_hands);
+        }
+
+        let part_one = get_winnings(&standard_hands);
+        let part_two = get_winnings(&joker_hands);
 
-    println!("Part one: {}", part_one);
-    println!("Part two: {}", part_two);
+        println!("Part one: {}", part_one);
+        println!("Part two: {}", part_two);
+    }
 
     println!("Elasped time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // brute force ranking to check the ordering against, sharing nothing with Hand but
+    // the category ladder: try every card each wildcard could stand in for and keep the
+    // strongest category that the resulting hand satisfies exactly
+    fn reference_key(cards: &str, rules: &Rules) -> Vec<u32> {
+        let cards = cards.chars().collect::<Vec<_>>();
+        let wild_positions = (0..cards.len())
+            .filter(|&i| rules.wildcards.contains(&cards[i]))
+            .collect::<Vec<_>>();
+
+        let mut best = 0;
+        let mut choice = vec![0; wild_positions.len()];
+        loop {
+            let mut filled = cards.clone();
+            for (&pos, &r) in wild_positions.iter().zip(choice.iter()) {
+                filled[pos] = rules.ranks[r];
+            }
+            let mut sizes = rules
+                .ranks
+                .iter()
+                .map(|r| filled.iter().filter(|c| *c == r).count())
+                .filter(|&n| n > 1)
+                .collect::<Vec<_>>();
+            sizes.sort_by(|a, b| b.cmp(a));
+            let mut idxs = filled
+                .iter()
+                .map(|c| rules.ranks.iter().position(|r| r == c).unwrap())
+                .collect::<Vec<_>>();
+            idxs.sort();
+
+            let satisfied = rules.categories.iter().rposition(|cat| match &cat.pattern {
+                Pattern::Groups(p) => {
+                    p.len() <= sizes.len() && p.iter().zip(sizes.iter()).all(|(a, b)| a <= b)
+                }
+                Pattern::Straight => idxs.windows(2).all(|w| w[1] == w[0] + 1),
+            });
+            best = best.max(satisfied.unwrap_or(0) as u32);
+
+            // advance to the next assignment of wildcards
+            let Some(i) = choice.iter().position(|&r| r + 1 < rules.ranks.len()) else {
+                break;
+            };
+            choice[i] += 1;
+            choice[..i].iter_mut().for_each(|r| *r = 0);
+        }
+
+        let mut key = vec![best];
+        // ties go card by card, wildcards lowest
+        let order = rules.wildcards.iter().chain(rules.ranks.iter());
+        key.extend(
+            cards
+                .iter()
+                .map(|c| order.clone().position(|o| o == c).unwrap() as u32),
+        );
+        key
+    }
+
+    // hands drawn at random from the rules' deck, in pairs that differ in a single card
+    // so that ties broken late in the hand are exercised
+    fn random_hands(rules: &Rules, count: usize) -> Vec<Hand> {
+        let deck = rules
+            .ranks
+            .iter()
+            .chain(rules.wildcards.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        let mut next_rand = move |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+
+        (0..count / 2)
+            .flat_map(|_| {
+                let mut cards = (0..rules.hand_size)
+                    .map(|_| deck[next_rand(deck.len())])
+                    .collect::<Vec<_>>();
+                let first = Hand::new(&cards.iter().collect::<String>(), 0, rules);
+                let pos = next_rand(cards.len());
+                cards[pos] = deck[next_rand(deck.len())];
+                let second = Hand::new(&cards.iter().collect::<String>(), 0, rules);
+                [first, second]
+            })
+            .collect()
+    }
+
+    // cmp must be antisymmetric, agree with eq and agree with the reference ranking on
+    // every pair. the reference is a plain lexicographic order, so agreeing with it
+    // also makes cmp transitive
+    fn check_ordering(hands: &[Hand], rules: &Rules) {
+        let keys = hands
+            .iter()
+            .map(|h| reference_key(&h.cards, rules))
+            .collect::<Vec<_>>();
+
+        for (i, a) in hands.iter().enumerate() {
+            for (j, b) in hands.iter().enumerate() {
+                let ord = a.cmp(b);
+                assert_eq!(
+                    ord,
+                    b.cmp(a).reverse(),
+                    "not antisymmetric: {} vs {}",
+                    a.cards,
+                    b.cards
+                );
+                assert_eq!(
+                    ord == Ordering::Equal,
+                    a == b,
+                    "cmp disagrees with eq: {} vs {}",
+                    a.cards,
+                    b.cards
+                );
+                assert_eq!(
+                    ord,
+                    keys[i].cmp(&keys[j]),
+                    "disagrees with reference: {} vs {} ({})",
+                    a.cards,
+                    b.cards,
+                    a.explain(b)
+                );
+            }
+        }
+    }
+
+    const EXAMPLE: [&str; 5] = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA"];
+
+    #[test]
+    fn standard_ordering() {
+        let rules = Rules::standard();
+        let mut hands = random_hands(&rules, 400);
+        hands.extend(EXAMPLE.iter().map(|h| Hand::new(h, 0, &rules)));
+        check_ordering(&hands, &rules);
+    }
+
+    #[test]
+    fn joker_ordering() {
+        let rules = Rules::with_jokers();
+        let mut hands = random_hands(&rules, 400);
+        hands.extend(EXAMPLE.iter().map(|h| Hand::new(h, 0, &rules)));
+        hands.push(Hand::new("JJJJJ", 0, &rules));
+        check_ordering(&hands, &rules);
+    }
+
+    #[test]
+    fn two_wildcard_six_card_ordering() {
+        let mut rules = Rules::new("23456789TQKA", "J*", 6);
+        rules
+            .insert_category(Category::straight(), "three of a kind")
+            .unwrap();
+        let mut hands = random_hands(&rules, 400);
+        hands.push(Hand::new("J*J*2*", 0, &rules));
+        hands.push(Hand::new("9TJQK*", 0, &rules));
+        check_ordering(&hands, &rules);
+    }
+
+    #[test]
+    fn example_winnings() {
+        let input = "32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483";
+        let hands = parse_input(input, &Rules::standard());
+        assert_eq!(get_winnings(&hands), 6440);
+        let hands = parse_input(input, &Rules::with_jokers());
+        assert_eq!(get_winnings(&hands), 5905);
+    }
+}
@@ -1,68 +1,500 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::time::Instant;
 
 const FILEPATH: &str = "./input/input.txt";
 
-fn ghost_navigate(insts: &Vec<usize>, nodes: &HashMap<String, Vec<String>>) -> u64 {
-    nodes
-        .keys()
-        .filter(|k| k.ends_with("A"))
-        .map(|start| navigate(start, true, insts, nodes) as u64)
-        .fold(1, |res, num| res * num / gcd(res, num)) // lcm calculation
+const MISSING: u32 = u32::MAX;
+
+// nodes are interned into dense ids. edges[id] holds the left and right destinations,
+// and jumps[id] is where a walk starting at id ends up after the whole instruction
+// string, with pass_hits[id] the offsets within that pass that land on an end node
+struct Map {
+    insts: Vec<usize>,
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+    defined: Vec<bool>,
+    edges: Vec<[u32; 2]>,
+    is_start: Vec<bool>,
+    is_end: Vec<bool>,
+    jumps: Vec<u32>,
+    pass_hits: Vec<Vec<u32>>,
+    bad_insts: Vec<(usize, char)>,
+    // nodes listed with other than two destinations, and how many they had
+    bad_dests: Vec<(u32, usize)>,
+}
+
+impl Map {
+    fn step(&self, loc: u32, inst_idx: usize) -> Result<u32, Outcome> {
+        let inst = self.insts[inst_idx];
+        match self.edges[loc as usize][inst] {
+            MISSING => Err(Outcome::Dangling {
+                node: self.names[loc as usize].clone(),
+                dest: format!("<no {} edge>", if inst == 0 { 'L' } else { 'R' }),
+            }),
+            dest if !self.defined[dest as usize] => Err(Outcome::Dangling {
+                node: self.names[loc as usize].clone(),
+                dest: self.names[dest as usize].clone(),
+            }),
+            dest => Ok(dest),
+        }
+    }
+
+    // applies the whole instruction string at once
+    fn jump(&self, loc: u32) -> Result<u32, Outcome> {
+        match self.jumps[loc as usize] {
+            MISSING => (0..self.insts.len()).try_fold(loc, |loc, i| self.step(loc, i)),
+            dest => Ok(dest),
+        }
+    }
+
+    fn fill_jumps(&mut self) {
+        let len = self.insts.len();
+        self.jumps = vec![MISSING; self.names.len()];
+        self.pass_hits = vec![vec![]; self.names.len()];
+        if len == 0 {
+            return;
+        }
+
+        for id in 0..self.names.len() as u32 {
+            if !self.defined[id as usize] {
+                continue;
+            }
+            let mut loc = id;
+            let mut hits = vec![];
+            let mut complete = true;
+            for k in 0..len {
+                if self.is_end[loc as usize] {
+                    hits.push(k as u32);
+                }
+                match self.step(loc, k) {
+                    Ok(next) => loc = next,
+                    Err(_) => {
+                        complete = false;
+                        break;
+                    }
+                }
+            }
+            if complete {
+                self.jumps[id as usize] = loc;
+            }
+            self.pass_hits[id as usize] = hits;
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as u32;
+        self.ids.insert(String::from(name), id);
+        self.names.push(String::from(name));
+        self.defined.push(false);
+        self.edges.push([MISSING; 2]);
+        self.is_start.push(name.ends_with('A'));
+        self.is_end.push(name.ends_with('Z'));
+        id
+    }
+
+    fn id(&self, name: &str) -> Option<u32> {
+        self.ids
+            .get(name)
+            .copied()
+            .filter(|&id| self.defined[id as usize])
+    }
+
+    fn ghost_starts(&self) -> Vec<u32> {
+        let mut starts = (0..self.names.len() as u32)
+            .filter(|&id| self.defined[id as usize] && self.is_start[id as usize])
+            .collect::<Vec<_>>();
+        starts.sort_by_key(|&id| &self.names[id as usize]);
+        starts
+    }
+}
+
+enum Outcome {
+    Arrived(u64),
+    // every (node, instruction index) state was revisited without reaching the target
+    Never { states: u64 },
+    Dangling { node: String, dest: String },
+    MissingStart(String),
+    NoInstructions,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Arrived(steps) => write!(f, "{}", steps),
+            Self::Never { states } => write!(f, "never (loops after {} states)", states),
+            Self::Dangling { node, dest } => {
+                write!(
+                    f,
+                    "stuck at {}, which points to missing node {}",
+                    node, dest
+                )
+            }
+            Self::MissingStart(start) => write!(f, "start node {} is not defined", start),
+            Self::NoInstructions => write!(f, "no instructions"),
+        }
+    }
+}
+
+// problems with a map that can be spotted before navigating it
+struct Diagnostics {
+    bad_insts: Vec<(usize, char)>,
+    dangling: Vec<(String, String)>,
+    missing_starts: Vec<String>,
+    missing_target: bool,
+    bad_dests: Vec<(String, usize)>,
+    idle: Vec<String>,
+}
+
+impl Diagnostics {
+    fn new(map: &Map) -> Self {
+        let mut dangling = vec![];
+        for id in (0..map.names.len()).filter(|&id| map.defined[id]) {
+            let name = &map.names[id];
+            for &dest in map.edges[id].iter() {
+                if dest != MISSING && !map.defined[dest as usize] {
+                    dangling.push((name.clone(), map.names[dest as usize].clone()));
+                }
+            }
+        }
+        dangling.sort();
+        dangling.dedup();
+        let mut bad_dests = map
+            .bad_dests
+            .iter()
+            .map(|&(id, count)| (map.names[id as usize].clone(), count))
+            .collect::<Vec<_>>();
+        bad_dests.sort();
+
+        let mut missing_starts = vec![];
+        if map.id("AAA").is_none() {
+            missing_starts.push(String::from("AAA"));
+        }
+        if map.ghost_starts().is_empty() {
+            missing_starts.push(String::from("**A"));
+        }
+
+        // nodes that no start ever steps away from when following the instructions.
+        // each pass through the instructions is walked once, from whichever start gets
+        // to its first node first, after which every start can skip it
+        let mut used = vec![false; map.names.len()];
+        let mut walked = vec![false; map.names.len()];
+        let mut starts = map.ghost_starts();
+        starts.extend(map.id("AAA"));
+        for start in starts {
+            let mut loc = start;
+            while !map.insts.is_empty() && !walked[loc as usize] {
+                walked[loc as usize] = true;
+                let mut at = loc;
+                for k in 0..map.insts.len() {
+                    used[at as usize] = true;
+                    match map.step(at, k) {
+                        Ok(next) => at = next,
+                        Err(_) => break,
+                    }
+                }
+                match map.jump(loc) {
+                    Ok(next) => loc = next,
+                    Err(_) => break,
+                }
+            }
+        }
+        let mut idle = (0..map.names.len())
+            .filter(|&id| map.defined[id] && !used[id])
+            .map(|id| map.names[id].clone())
+            .collect::<Vec<_>>();
+        idle.sort();
+
+        Self {
+            bad_insts: map.bad_insts.clone(),
+            dangling,
+            missing_starts,
+            missing_target: map.id("ZZZ").is_none(),
+            bad_dests,
+            idle,
+        }
+    }
+
+    fn print(&self) {
+        for (i, ch) in self.bad_insts.iter() {
+            println!("Unknown instruction '{}' at position {} (ignored)", ch, i);
+        }
+        for (node, dest) in self.dangling.iter() {
+            println!("Node {} points to undefined node {}", node, dest);
+        }
+        for (node, count) in self.bad_dests.iter() {
+            println!("Node {} has {} destinations, expected 2", node, count);
+        }
+        for start in self.missing_starts.iter() {
+            println!("No start node {}", start);
+        }
+        if self.missing_target {
+            println!("No target node ZZZ");
+        }
+        if !self.idle.is_empty() {
+            println!(
+                "{} nodes are never left by any start: {}",
+                self.idle.len(),
+                self.idle.join(", ")
+            );
+        }
+    }
+}
+
+// the path of a ghost through (node, instruction index) states: a prefix of `mu` steps
+// followed by a cycle of `lambda` steps. `hits` are the steps in the first pass
+// (before mu + lambda) at which the ghost stands on a Z node. the walk is done a whole
+// instruction string at a time, so mu and lambda are multiples of its length
+struct GhostCycle {
+    mu: u64,
+    lambda: u64,
+    hits: Vec<u64>,
+}
+
+// a ghost that walks off the map: the steps at which it stands on a Z node before then,
+// and the step at which it gets stuck
+struct GhostDeath {
+    hits: Vec<u64>,
+    step: u64,
+    outcome: Outcome,
+}
+
+impl GhostCycle {
+    fn find(start: u32, map: &Map) -> Result<Self, GhostDeath> {
+        let len = map.insts.len() as u64;
+        let mut seen = vec![None; map.names.len()];
+        let mut hits = vec![];
+        let mut loc = start;
+
+        for pass in 0.. {
+            if let Some(mu) = seen[loc as usize] {
+                return Ok(Self {
+                    mu: mu * len,
+                    lambda: (pass - mu) * len,
+                    hits,
+                });
+            }
+            seen[loc as usize] = Some(pass);
+            hits.extend(
+                map.pass_hits[loc as usize]
+                    .iter()
+                    .map(|&k| pass * len + k as u64),
+            );
+            loc = match map.jump(loc) {
+                Ok(next) => next,
+                Err(_) => {
+                    let (k, outcome) = (0..map.insts.len())
+                        .try_fold(loc, |loc, k| map.step(loc, k).map_err(|err| (k, err)))
+                        .unwrap_err();
+                    return Err(GhostDeath {
+                        hits,
+                        step: pass * len + k as u64,
+                        outcome,
+                    });
+                }
+            };
+        }
+
+        unreachable!()
+    }
+
+    fn is_hit(&self, step: u64) -> bool {
+        let step = if step < self.mu {
+            step
+        } else {
+            self.mu + (step - self.mu) % self.lambda
+        };
+        self.hits.binary_search(&step).is_ok()
+    }
+
+    fn cycle_hits(&self) -> impl Iterator<Item = u64> + '_ {
+        self.hits.iter().copied().filter(|&h| h >= self.mu)
+    }
+}
+
+// the earliest step at which every ghost is on a Z node simultaneously, if that ever
+// happens. steps before every ghost has entered its cycle are checked
+// directly; after that each ghost contributes a set of congruences step = hit (mod
+// lambda), which are combined with the chinese remainder theorem
+fn ghost_navigate(map: &Map) -> Outcome {
+    if map.insts.is_empty() {
+        return Outcome::NoInstructions;
+    }
+    let starts = map.ghost_starts();
+    if starts.is_empty() {
+        return Outcome::MissingStart(String::from("**A"));
+    }
+    let mut cycles = vec![];
+    let mut deaths = vec![];
+    for &start in starts.iter() {
+        match GhostCycle::find(start, map) {
+            Ok(cycle) => cycles.push(cycle),
+            Err(death) => deaths.push(death),
+        }
+    }
+
+    // nothing can happen after the first ghost gets stuck, so only the steps it stood on
+    // a Z node can work, and it is only stuck if none of them do
+    if let Some(first) = (0..deaths.len()).min_by_key(|&i| deaths[i].step) {
+        return deaths[first]
+            .hits
+            .iter()
+            .copied()
+            .find(|&step| {
+                deaths.iter().all(|d| d.hits.binary_search(&step).is_ok())
+                    && cycles.iter().all(|c| c.is_hit(step))
+            })
+            .map(Outcome::Arrived)
+            .unwrap_or_else(|| deaths.swap_remove(first).outcome);
+    }
+
+    let settled = cycles.iter().map(|c| c.mu).max().unwrap_or(0);
+    if let Some(step) = (0..settled).find(|&step| cycles.iter().all(|c| c.is_hit(step))) {
+        return Outcome::Arrived(step);
+    }
+
+    let mut solutions = vec![(0, 1)];
+    for cycle in cycles.iter() {
+        solutions = solutions
+            .iter()
+            .flat_map(|&(a, m)| {
+                cycle
+                    .cycle_hits()
+                    .filter_map(move |hit| crt(a, m, hit as i128, cycle.lambda as i128))
+            })
+            .collect();
+        solutions.sort();
+        solutions.dedup();
+    }
+
+    // smallest step congruent to each solution that comes after every prefix
+    solutions
+        .iter()
+        .map(|&(a, m)| {
+            let settled = settled as i128;
+            let offset = (a - settled).rem_euclid(m);
+            (settled + offset) as u64
+        })
+        .min()
+        .map(Outcome::Arrived)
+        .unwrap_or(Outcome::Never {
+            states: cycles.iter().map(|c| c.mu + c.lambda).sum(),
+        })
+}
+
+// solves x = a1 (mod m1), x = a2 (mod m2) for moduli that need not be coprime,
+// returning (x, lcm(m1, m2))
+fn crt(a1: i128, m1: i128, a2: i128, m2: i128) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+    let lcm = m1 / g * m2;
+    let x = a1 + m1 * ((a2 - a1) / g * p % (m2 / g));
+
+    Some((x.rem_euclid(lcm), lcm))
 }
 
-fn gcd(a: u64, b: u64) -> u64 {
-    let mut a = a;
-    let mut b = b;
-    while b > 0 {
-        let t = b;
-        b = a % b;
-        a = t;
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
     }
-    a
 }
 
-fn navigate(
-    start: &str,
-    spooky: bool,
-    insts: &Vec<usize>,
-    nodes: &HashMap<String, Vec<String>>,
-) -> usize {
+// walks from start until reaching the target, stopping as soon as a (node, instruction
+// index) state repeats since the walk is then stuck in a loop that misses the target.
+// passes through the instruction string that visit no end node are skipped in one jump
+fn navigate(start: &str, spooky: bool, map: &Map) -> Outcome {
+    let Some(start) = map.id(start) else {
+        return Outcome::MissingStart(String::from(start));
+    };
+    if map.insts.is_empty() {
+        return Outcome::NoInstructions;
+    }
+    let target = map.id("ZZZ");
+    let len = map.insts.len() as u64;
+
+    let mut seen = vec![false; map.names.len()];
     let mut loc = start;
-    for i in 0.. {
-        if loc == "ZZZ" || (spooky && loc.ends_with("Z")) {
-            return i;
+    for pass in 0.. {
+        if seen[loc as usize] {
+            return Outcome::Never { states: pass * len };
+        }
+        seen[loc as usize] = true;
+
+        if map.pass_hits[loc as usize].is_empty() {
+            loc = match map.jump(loc) {
+                Ok(next) => next,
+                Err(outcome) => return outcome,
+            };
+            continue;
+        }
+        for k in 0..map.insts.len() {
+            if Some(loc) == target || (spooky && map.is_end[loc as usize]) {
+                return Outcome::Arrived(pass * len + k as u64);
+            }
+            loc = match map.step(loc, k) {
+                Ok(next) => next,
+                Err(outcome) => return outcome,
+            };
         }
-        loc = &nodes[loc][insts[i % insts.len()]];
     }
 
-    panic!()
+    unreachable!()
 }
 
-fn parse_input(raw_input: &str) -> (Vec<usize>, HashMap<String, Vec<String>>) {
+fn parse_input(raw_input: &str) -> Map {
     let pcs = raw_input.trim().split("\n\n").collect::<Vec<_>>();
 
-    let insts = pcs[0]
-        .chars()
-        .map(|c| if c == 'L' { 0 } else { 1 })
-        .collect::<Vec<_>>();
-
-    let nodes = pcs[1]
-        .trim()
-        .lines()
-        .map(|line| {
-            let sub_pcs = line.split("=").collect::<Vec<_>>();
-            let src = String::from(sub_pcs[0].trim());
-            let dests = sub_pcs[1]
-                .split(",")
-                .map(|d| String::from(d.trim().replace("(", "").replace(")", "")))
-                .collect::<Vec<_>>();
-            (src, dests)
-        })
-        .collect::<HashMap<_, _>>();
+    let mut insts = vec![];
+    let mut bad_insts = vec![];
+    for (i, c) in pcs[0].trim().chars().enumerate() {
+        match c {
+            'L' => insts.push(0),
+            'R' => insts.push(1),
+            _ => bad_insts.push((i, c)),
+        }
+    }
+
+    let mut map = Map {
+        insts,
+        ids: HashMap::new(),
+        names: vec![],
+        defined: vec![],
+        edges: vec![],
+        is_start: vec![],
+        is_end: vec![],
+        jumps: vec![],
+        pass_hits: vec![],
+        bad_insts,
+        bad_dests: vec![],
+    };
+    for line in pcs.get(1).unwrap_or(&"").trim().lines() {
+        let (src, dests) = line.split_once('=').unwrap_or((line, ""));
+        let src = map.intern(src.trim());
+        map.defined[src as usize] = true;
+        let dests = dests
+            .split(',')
+            .map(|d| d.trim().replace(['(', ')'], ""))
+            .filter(|d| !d.is_empty())
+            .collect::<Vec<_>>();
+        if dests.len() != 2 {
+            map.bad_dests.push((src, dests.len()));
+        }
+        for (i, dest) in dests.iter().take(2).enumerate() {
+            map.edges[src as usize][i] = map.intern(dest);
+        }
+    }
+    map.fill_jumps();
 
-    (insts, nodes)
+    map
 }
 
 fn main() {
@@ -72,10 +504,11 @@ fn main() {
     let now = Instant::now();
 
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let (insts, nodes) = parse_input(&raw_input);
+    let map = parse_input(&raw_input);
+    Diagnostics::new(&map).print();
 
-    let part_one = navigate("AAA", false, &insts, &nodes);
-    let part_two = ghost_navigate(&insts, &nodes);
+    let part_one = navigate("AAA", false, &map);
+    let part_two = ghost_navigate(&map);
 
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
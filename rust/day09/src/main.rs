@@ -1,55 +1,390 @@
+use std::env;
 use std::fs;
+use std::str::FromStr;
 use std::time::Instant;
 
 const FILEPATH: &str = "./input/input.txt";
 
-fn extrapolate(vals: &Vec<i32>) -> (i32, i32) {
-    if vals.iter().all(|&v| v == 0) {
-        (0, 0)
-    } else {
-        let diffs = vals
-            .iter()
-            .zip(vals.iter().skip(1))
-            .map(|(val, next_val)| next_val - val)
+// a history as the polynomial through its points, kept in newton form: the leading
+// entries of the finite difference table, up to and including the last nonzero row
+struct Extrapolator {
+    diffs: Vec<i128>,
+    len: usize,
+}
+
+impl Extrapolator {
+    fn new(vals: &[i128]) -> Result<Self, String> {
+        let mut row = vals.to_vec();
+        let mut diffs = vec![];
+
+        while !row.iter().all(|&v| v == 0) {
+            diffs.push(row[0]);
+            // a lone reading is a constant, but a longer history that runs out of
+            // differences before they reach zero could be any degree from len - 1 up
+            if row.len() == 1 {
+                if vals.len() == 1 {
+                    break;
+                }
+                return Err(format!(
+                    "differences never reach all zeros, so the degree is at least {}",
+                    vals.len() - 1
+                ));
+            }
+            row = row.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+
+        Ok(Self {
+            diffs,
+            len: vals.len(),
+        })
+    }
+
+    // degree of the fitted polynomial, with an all zero history counting as degree 0
+    fn degree(&self) -> usize {
+        self.diffs.len().saturating_sub(1)
+    }
+
+    // value at any integer position, where 0 is the first reading. uses
+    // f(x) = sum of diffs[k] * C(x, k), which holds for negative x as well
+    fn value_at(&self, x: i128) -> Option<i128> {
+        let mut binom: i128 = 1;
+        let mut total: i128 = 0;
+        for (k, &diff) in self.diffs.iter().enumerate() {
+            if k > 0 {
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, which divides exactly
+                binom = binom.checked_mul(x - k as i128 + 1)? / k as i128;
+            }
+            total = total.checked_add(diff.checked_mul(binom)?)?;
+        }
+        Some(total)
+    }
+
+    fn forward(&self, steps: i128) -> Option<i128> {
+        self.value_at(self.len as i128 - 1 + steps)
+    }
+
+    fn backward(&self, steps: i128) -> Option<i128> {
+        self.value_at(-steps)
+    }
+}
+
+// a least squares polynomial through noisy readings. it's fitted in the variable
+// t = (x - center) / scale using vandermonde with arnoldi, so the polynomial is held in
+// an orthogonal basis (described by the recurrence in `h`) rather than as raw powers,
+// which stays accurate at high degree. `residual` is the root mean square error
+struct Fit {
+    weights: Vec<f64>,
+    h: Vec<Vec<f64>>,
+    center: f64,
+    scale: f64,
+    residual: f64,
+}
+
+impl Fit {
+    fn new(vals: &[f64], degree: usize) -> Self {
+        let n = vals.len();
+        let center = (n as f64 - 1.0) / 2.0;
+        let scale = center.max(1.0);
+        let ts = (0..n)
+            .map(|x| (x as f64 - center) / scale)
             .collect::<Vec<_>>();
-        let (first, last) = extrapolate(&diffs);
-        (vals[0] - first, vals[vals.len() - 1] + last)
+
+        let mut q = vec![vec![1.0; n]];
+        let mut h = vec![vec![0.0; degree]; degree + 1];
+        for k in 1..=degree {
+            let mut v = ts
+                .iter()
+                .zip(q[k - 1].iter())
+                .map(|(t, p)| t * p)
+                .collect::<Vec<_>>();
+            // orthogonalise twice, which keeps the basis orthogonal in floating point
+            for _ in 0..2 {
+                for (j, qj) in q.iter().enumerate() {
+                    let proj = dot(qj, &v) / n as f64;
+                    h[j][k - 1] += proj;
+                    v.iter_mut()
+                        .zip(qj.iter())
+                        .for_each(|(a, b)| *a -= proj * b);
+                }
+            }
+            h[k][k - 1] = (dot(&v, &v) / n as f64).sqrt();
+            q.push(v.iter().map(|a| a / h[k][k - 1]).collect());
+        }
+        let weights = q.iter().map(|qk| dot(qk, vals) / n as f64).collect();
+
+        let mut fit = Self {
+            weights,
+            h,
+            center,
+            scale,
+            residual: 0.0,
+        };
+        let sq_err = vals
+            .iter()
+            .enumerate()
+            .map(|(x, v)| (fit.value_at(x as f64) - v).powi(2))
+            .sum::<f64>();
+        fit.residual = (sq_err / n as f64).sqrt();
+
+        fit
+    }
+
+    // the lowest degree fit within tolerance, or the closest fit up to max_degree
+    // along with false if none is close enough to count as a polynomial. tolerance
+    // is relative to the largest reading. the degree stops short of len - 1, since
+    // that fit passes through every reading and so always has a residual of zero
+    fn best(vals: &[f64], tolerance: f64, max_degree: usize) -> (Self, bool) {
+        let max_degree = max_degree.min(vals.len().saturating_sub(2));
+        let magnitude = vals.iter().fold(1.0f64, |m, v| m.max(v.abs()));
+        let mut best: Option<Self> = None;
+        for degree in 0..=max_degree {
+            let fit = Self::new(vals, degree);
+            if fit.residual <= tolerance * magnitude {
+                return (fit, true);
+            }
+            if best.as_ref().is_none_or(|b| fit.residual < b.residual) {
+                best = Some(fit);
+            }
+        }
+
+        (best.unwrap(), false)
+    }
+
+    fn degree(&self) -> usize {
+        self.weights.len() - 1
+    }
+
+    fn value_at(&self, x: f64) -> f64 {
+        let t = (x - self.center) / self.scale;
+        let mut basis: Vec<f64> = vec![1.0];
+        for k in 1..self.weights.len() {
+            let next = t * basis[k - 1] - (0..k).map(|j| self.h[j][k - 1] * basis[j]).sum::<f64>();
+            basis.push(next / self.h[k][k - 1]);
+        }
+
+        dot(&basis, &self.weights)
+    }
+
+    // coefficients of the polynomial in x itself, constant term first
+    fn coefficients(&self) -> Vec<f64> {
+        // expand each basis polynomial in powers of t, then substitute t = (x - c) / s
+        let mut basis: Vec<Vec<f64>> = vec![vec![1.0]];
+        for k in 1..self.weights.len() {
+            let mut next = vec![0.0; k + 1];
+            for (i, b) in basis[k - 1].iter().enumerate() {
+                next[i + 1] += b;
+            }
+            for (j, poly) in basis.iter().enumerate() {
+                for (i, b) in poly.iter().enumerate() {
+                    next[i] -= self.h[j][k - 1] * b;
+                }
+            }
+            basis.push(next.iter().map(|b| b / self.h[k][k - 1]).collect());
+        }
+        let mut in_t = vec![0.0; self.weights.len()];
+        for (w, poly) in self.weights.iter().zip(basis.iter()) {
+            for (i, b) in poly.iter().enumerate() {
+                in_t[i] += w * b;
+            }
+        }
+
+        in_t.iter().rev().fold(vec![], |poly, &c| {
+            // poly * (x - center) / scale + c
+            let mut next = vec![0.0; poly.len() + 1];
+            for (k, p) in poly.iter().enumerate() {
+                next[k + 1] += p / self.scale;
+                next[k] -= p * self.center / self.scale;
+            }
+            next[0] += c;
+            next
+        })
+    }
+
+    fn describe(&self) -> String {
+        self.coefficients()
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(k, c)| match k {
+                0 => format!("{:.4}", c),
+                1 => format!("{:.4}x", c),
+                _ => format!("{:.4e}x^{}", c, k),
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
     }
 }
 
-fn solve(vals: &Vec<Vec<i32>>) -> (i32, i32) {
-    vals.iter().fold((0, 0), |(first, last), vals| {
-        let (a, b) = extrapolate(&vals);
-        (first + a, last + b)
-    })
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+// sums the predictions `steps` before and after every history
+fn solve(histories: &[Extrapolator], behind: i128, ahead: i128) -> (Option<i128>, Option<i128>) {
+    let total = |f: &dyn Fn(&Extrapolator) -> Option<i128>| {
+        histories
+            .iter()
+            .try_fold(0i128, |acc, h| acc.checked_add(f(h)?))
+    };
+
+    (total(&|h| h.backward(behind)), total(&|h| h.forward(ahead)))
+}
+
+fn parse_input(raw_input: &str) -> Vec<Vec<i128>> {
+    raw_input
+        .trim()
+        .lines()
+        .map(|line| {
+            line.split_whitespace()
+                .map(|pc| pc.parse::<i128>().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
 }
 
-fn parse_input(raw_input: &str) -> Vec<Vec<i32>> {
+fn parse_readings(raw_input: &str) -> Vec<Vec<f64>> {
     raw_input
         .trim()
         .lines()
         .map(|line| {
-            line.trim()
-                .split_whitespace()
-                .map(|pc| pc.trim().parse::<i32>().unwrap())
+            line.split_whitespace()
+                .map(|pc| pc.parse::<f64>().unwrap())
                 .collect::<Vec<_>>()
         })
         .collect::<Vec<_>>()
 }
 
+// fits each noisy history and sums the predictions like solve does
+fn solve_noisy(
+    raw_input: &str,
+    tolerance: f64,
+    max_degree: Option<usize>,
+    behind: i128,
+    ahead: i128,
+) {
+    let mut first = 0.0;
+    let mut last = 0.0;
+    for (i, vals) in parse_readings(raw_input).iter().enumerate() {
+        if vals.len() < 2 {
+            println!(
+                "History {}: undetermined, {} reading(s) can't show a polynomial",
+                i + 1,
+                vals.len()
+            );
+            continue;
+        }
+        let max_degree = max_degree.unwrap_or(usize::MAX).min(vals.len() - 2);
+        let (fit, polynomial) = Fit::best(vals, tolerance, max_degree);
+        let before = fit.value_at(-behind as f64);
+        let after = fit.value_at((vals.len() as i128 - 1 + ahead) as f64);
+
+        if polynomial {
+            println!(
+                "History {}: degree {}, residual {:.3e}, p(x) = {}",
+                i + 1,
+                fit.degree(),
+                fit.residual,
+                fit.describe()
+            );
+        } else {
+            println!(
+                "History {}: not polynomial up to degree {} (best residual {:.3e} at degree {}), p(x) = {}",
+                i + 1,
+                max_degree,
+                fit.residual,
+                fit.degree(),
+                fit.describe()
+            );
+        }
+        println!(
+            "    {} behind: {:.4}, {} ahead: {:.4}",
+            behind, before, ahead, after
+        );
+
+        first += before;
+        last += after;
+    }
+
+    println!("Part one: {:.4}", last);
+    println!("Part two: {:.4}", first);
+}
+
+fn parse_arg<T: FromStr>(flag: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("Bad value {} for {}", value, flag))
+}
+
+fn show(val: Option<i128>) -> String {
+    val.map(|v| v.to_string())
+        .unwrap_or(String::from("overflow"))
+}
+
 fn main() {
     println!("Advent of Code 2023");
     println!("Day 9: Mirage Maintenance");
 
     let now = Instant::now();
 
+    // --ahead N and --behind N forecast further than one step, --detail shows each history.
+    // --noisy fits floating point readings instead, accepting the lowest degree whose
+    // error relative to the largest reading is within --tolerance (and at most --max-degree)
+    let mut ahead = 1;
+    let mut behind = 1;
+    let mut detail = false;
+    let mut noisy = false;
+    let mut tolerance = 1e-12;
+    let mut max_degree = None;
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "--ahead" => ahead = parse_arg(&flag, &value()),
+            "--behind" => behind = parse_arg(&flag, &value()),
+            "--tolerance" => tolerance = parse_arg(&flag, &value()),
+            "--max-degree" => max_degree = Some(parse_arg(&flag, &value())),
+            "--detail" => detail = true,
+            "--noisy" => noisy = true,
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let histories = parse_input(&raw_input);
 
-    let (part_two, part_one) = solve(&histories);
+    if noisy {
+        solve_noisy(&raw_input, tolerance, max_degree, behind, ahead);
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
+
+    let mut histories = vec![];
+    for (i, vals) in parse_input(&raw_input).iter().enumerate() {
+        match Extrapolator::new(vals) {
+            Ok(h) => {
+                if detail {
+                    println!(
+                        "History {}: degree {}, {} behind: {}, {} ahead: {}",
+                        i + 1,
+                        h.degree(),
+                        behind,
+                        show(h.backward(behind)),
+                        ahead,
+                        show(h.forward(ahead))
+                    );
+                }
+                histories.push(h);
+            }
+            Err(err) => println!("History {} skipped: {}", i + 1, err),
+        }
+    }
+
+    let (part_two, part_one) = solve(&histories, behind, ahead);
 
-    println!("Part one: {}", part_one);
-    println!("Part two: {}", part_two);
+    println!("Part one: {}", show(part_one));
+    println!("Part two: {}", show(part_two));
 
     println!("Elasped time: {}ms", now.elapsed().as_millis());
 }
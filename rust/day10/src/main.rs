@@ -1,6 +1,12 @@
+use std::env;
+use std::fmt;
 use std::fs;
 use std::time::Instant;
 
+mod network;
+
+use network::PipeNetwork;
+
 const FILEPATH: &str = "./input/input.txt";
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -14,37 +20,301 @@ enum Tile {
     Ground,
 }
 
-fn find_main_loop(initial_pos: (usize, usize), grid: &Vec<Vec<Tile>>) -> Vec<(usize, usize)> {
-    let (mut r, mut c) = initial_pos;
+impl Tile {
+    // whether the tile connects north, east, south and west
+    fn connections(&self) -> [bool; 4] {
+        match self {
+            Tile::Vertical => [true, false, true, false],
+            Tile::Horizontal => [false, true, false, true],
+            Tile::NorthEast => [true, true, false, false],
+            Tile::NorthWest => [true, false, false, true],
+            Tile::SouthEast => [false, true, true, false],
+            Tile::SouthWest => [false, false, true, true],
+            Tile::Ground => [false; 4],
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Vertical => '│',
+            Tile::Horizontal => '─',
+            Tile::NorthEast => '└',
+            Tile::NorthWest => '┘',
+            Tile::SouthEast => '┌',
+            Tile::SouthWest => '┐',
+            Tile::Ground => '·',
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Region {
+    Loop,
+    Inside,
+    Outside,
+}
+
+// classifies every tile by scanning each row and flipping between outside and inside
+// whenever the loop is crossed. only loop tiles with a northern connection count as a
+// crossing, so running along a horizontal stretch of the loop is handled correctly
+fn classify(grid: &[Vec<Tile>], main_loop: &[(usize, usize)]) -> Vec<Vec<Region>> {
+    let mut on_loop = vec![vec![false; grid[0].len()]; grid.len()];
+    for &(r, c) in main_loop {
+        on_loop[r][c] = true;
+    }
+
+    grid.iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let mut inside = false;
+            row.iter()
+                .enumerate()
+                .map(|(c, tile)| {
+                    if on_loop[r][c] {
+                        if tile.connections()[0] {
+                            inside = !inside;
+                        }
+                        Region::Loop
+                    } else if inside {
+                        Region::Inside
+                    } else {
+                        Region::Outside
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn render(grid: &[Vec<Tile>], regions: &[Vec<Region>]) -> String {
+    // the grid is padded with a border of ground, which isn't drawn
+    let rows = 1..grid.len() - 1;
+    let cols = 1..grid[0].len() - 1;
+
+    rows.map(|r| {
+        // only switch colour when the region changes
+        let mut last = None;
+        let mut line = String::new();
+        for c in cols.clone() {
+            let region = regions[r][c];
+            if last != Some(region) {
+                line.push_str(match region {
+                    Region::Loop => "\x1b[0;1;33m",
+                    Region::Inside => "\x1b[0;30;42m",
+                    Region::Outside => "\x1b[0;2m",
+                });
+                last = Some(region);
+            }
+            line.push(grid[r][c].symbol());
+        }
+        line.push_str("\x1b[0m\n");
+        line
+    })
+    .collect()
+}
+
+fn render_svg(grid: &[Vec<Tile>], regions: &[Vec<Region>]) -> String {
+    const CELL: usize = 10;
+    let height = (grid.len() - 2) * CELL;
+    let width = (grid[0].len() - 2) * CELL;
 
-    let (mut d_r, mut d_c) = match grid[r][c] {
-        Tile::Vertical | Tile::SouthEast | Tile::SouthWest => (1isize, 0isize),
-        Tile::NorthEast | Tile::NorthWest => (-1, 0),
-        Tile::Horizontal => (0, 1),
-        Tile::Ground => panic!(),
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+        width, height
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        width, height
+    ));
+
+    for r in 1..grid.len() - 1 {
+        for c in 1..grid[0].len() - 1 {
+            let (x, y) = ((c - 1) * CELL, (r - 1) * CELL);
+            let (stroke, stroke_width) = match regions[r][c] {
+                Region::Loop => ("crimson", 3),
+                Region::Inside => {
+                    svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"palegreen\"/>\n",
+                        x, y, CELL, CELL
+                    ));
+                    ("gray", 1)
+                }
+                Region::Outside => ("lightgray", 1),
+            };
+
+            let (cx, cy) = (x + CELL / 2, y + CELL / 2);
+            let ends = [(cx, y), (x + CELL, cy), (cx, y + CELL), (x, cy)];
+            for (end, _) in ends
+                .iter()
+                .zip(grid[r][c].connections())
+                .filter(|(_, connected)| *connected)
+            {
+                svg.push_str(&format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\"/>\n",
+                    cx, cy, end.0, end.1, stroke, stroke_width
+                ));
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+type Grid = Vec<Vec<Tile>>;
+
+const DELTAS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+const PIPES: [Tile; 6] = [
+    Tile::Vertical,
+    Tile::Horizontal,
+    Tile::NorthEast,
+    Tile::NorthWest,
+    Tile::SouthEast,
+    Tile::SouthWest,
+];
+
+enum MazeError {
+    BadTile {
+        ch: char,
+        pos: (usize, usize),
+    },
+    NoStart,
+    ManyStarts(Vec<(usize, usize)>),
+    // the start doesn't have two neighbours connecting to it
+    StartUnconnected,
+    AmbiguousStart(Vec<Tile>),
+    // following the pipes from `from` leads to a tile at `at` that doesn't connect back
+    Broken {
+        at: (usize, usize),
+        from: (usize, usize),
+    },
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // positions are stored in padded grid coordinates, but reported as in the input
+        let show =
+            |(r, c): (usize, usize)| format!("row {} col {}", r as isize - 1, c as isize - 1);
+        match self {
+            Self::BadTile { ch, pos } => write!(f, "unknown tile '{}' at {}", ch, show(*pos)),
+            Self::NoStart => write!(f, "no start tile S"),
+            Self::ManyStarts(starts) => write!(
+                f,
+                "several start tiles: {}",
+                starts
+                    .iter()
+                    .map(|&p| show(p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::StartUnconnected => write!(f, "fewer than two pipes connect to S"),
+            Self::AmbiguousStart(tiles) => write!(
+                f,
+                "S could be any of {}",
+                tiles
+                    .iter()
+                    .map(|t| t.symbol().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Self::Broken { at, from } => write!(
+                f,
+                "loop is broken at {} (reached from {})",
+                show(*at),
+                show(*from)
+            ),
+        }
+    }
+}
+
+// walks the loop through initial_pos, returning its tiles ending with initial_pos
+fn find_main_loop(
+    initial_pos: (usize, usize),
+    grid: &[Vec<Tile>],
+) -> Result<Vec<(usize, usize)>, MazeError> {
+    let step = |(r, c): (usize, usize), dir: usize| {
+        let (d_r, d_c) = DELTAS[dir];
+        let next = (r as isize + d_r, c as isize + d_c);
+        let in_grid = next.0 >= 0
+            && next.1 >= 0
+            && (next.0 as usize) < grid.len()
+            && (next.1 as usize) < grid[0].len();
+        in_grid.then_some((next.0 as usize, next.1 as usize))
     };
+
+    let mut pos = initial_pos;
+    let mut dir = grid[pos.0][pos.1]
+        .connections()
+        .iter()
+        .position(|&conn| conn)
+        .ok_or(MazeError::StartUnconnected)?;
     let mut main_loop = vec![];
 
     loop {
-        (r, c) = ((r as isize + d_r) as usize, (c as isize + d_c) as usize);
-        main_loop.push((r, c));
+        let from = pos;
+        pos = step(pos, dir).ok_or(MazeError::Broken { at: pos, from })?;
+        let conns = grid[pos.0][pos.1].connections();
+        if !conns[(dir + 2) % 4] {
+            return Err(MazeError::Broken { at: pos, from });
+        }
+        main_loop.push(pos);
 
-        if (r, c) == initial_pos {
+        if pos == initial_pos {
             break;
         }
 
-        (d_r, d_c) = match grid[r][c] {
-            Tile::Vertical | Tile::Horizontal => (d_r, d_c),
-            Tile::NorthEast | Tile::SouthWest => (d_c, d_r),
-            Tile::NorthWest | Tile::SouthEast => (-d_c, -d_r),
-            _ => panic!(),
-        };
+        dir = (0..4).find(|&d| conns[d] && d != (dir + 2) % 4).unwrap();
+    }
+
+    Ok(main_loop)
+}
+
+// tries every pipe shape in place of S and keeps those that close a loop
+fn infer_start(start: (usize, usize), grid: &mut [Vec<Tile>]) -> Result<Tile, MazeError> {
+    let (r, c) = start;
+
+    // shapes whose two neighbours both connect back to the start
+    let plausible = PIPES
+        .iter()
+        .copied()
+        .filter(|tile| {
+            tile.connections()
+                .iter()
+                .enumerate()
+                .filter(|(_, &conn)| conn)
+                .all(|(dir, _)| {
+                    let (d_r, d_c) = DELTAS[dir];
+                    let (n_r, n_c) = ((r as isize + d_r) as usize, (c as isize + d_c) as usize);
+                    grid[n_r][n_c].connections()[(dir + 2) % 4]
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let mut closed = vec![];
+    let mut first_break = None;
+    for &tile in plausible.iter() {
+        grid[r][c] = tile;
+        match find_main_loop(start, grid) {
+            Ok(_) => closed.push(tile),
+            Err(err) => {
+                first_break.get_or_insert(err);
+            }
+        }
     }
+    grid[r][c] = Tile::Ground;
 
-    main_loop
+    match closed.len() {
+        1 => {
+            grid[r][c] = closed[0];
+            Ok(closed[0])
+        }
+        0 => Err(first_break.unwrap_or(MazeError::StartUnconnected)),
+        _ => Err(MazeError::AmbiguousStart(closed)),
+    }
 }
 
-fn measure_loop(polygon: &Vec<(usize, usize)>) -> (usize, usize) {
+fn measure_loop(polygon: &[(usize, usize)]) -> (usize, usize) {
     let perim = polygon.len();
     let area = polygon
         .iter()
@@ -55,30 +325,30 @@ fn measure_loop(polygon: &Vec<(usize, usize)>) -> (usize, usize) {
             (c1 * r2) as i32 - (c2 * r1) as i32 // shoelace formula
         })
         .sum::<i32>()
+        .abs()
         / 2;
     (perim, area as usize)
 }
 
-fn parse_input(raw_input: &str) -> ((usize, usize), Vec<Vec<Tile>>) {
+fn parse_input(raw_input: &str) -> Result<((usize, usize), Grid), MazeError> {
     let chars = raw_input
         .trim()
         .lines()
         .map(|line| line.trim().chars().collect::<Vec<_>>())
         .collect::<Vec<_>>();
 
-    let num_rows = chars.len();
-    let num_cols = chars[0].len();
+    let num_cols = chars.iter().map(|line| line.len()).max().unwrap_or(0);
 
     let mut grid = vec![];
     grid.push(vec![Tile::Ground; num_cols + 2]);
 
-    let mut starting_pos = None;
+    let mut starts = vec![];
 
-    for r in 0..num_rows {
+    for (r, line) in chars.iter().enumerate() {
         let mut row = vec![];
         row.push(Tile::Ground);
-        for c in 0..num_cols {
-            let tile = match chars[r][c] {
+        for (c, &ch) in line.iter().enumerate() {
+            let tile = match ch {
                 '|' => Tile::Vertical,
                 '-' => Tile::Horizontal,
                 'L' => Tile::NorthEast,
@@ -87,47 +357,79 @@ fn parse_input(raw_input: &str) -> ((usize, usize), Vec<Vec<Tile>>) {
                 'F' => Tile::SouthEast,
                 '.' => Tile::Ground,
                 'S' => {
-                    starting_pos = Some((r + 1, c + 1));
+                    starts.push((r + 1, c + 1));
                     Tile::Ground // we'll fix below
                 }
-                _ => panic!(""),
+                _ => {
+                    return Err(MazeError::BadTile {
+                        ch,
+                        pos: (r + 1, c + 1),
+                    })
+                }
             };
             row.push(tile);
         }
-        row.push(Tile::Ground);
+        row.resize(num_cols + 2, Tile::Ground);
         grid.push(row);
     }
 
     grid.push(vec![Tile::Ground; num_cols + 2]);
 
-    let (r, c) = starting_pos.unwrap();
-    let up = grid[r - 1][c];
-    let down = grid[r + 1][c];
-    let left = grid[r][c - 1];
-    let right = grid[r][c + 1];
-
-    let starting_tile = if up == Tile::Vertical || up == Tile::SouthEast || up == Tile::SouthWest {
-        if left == Tile::Horizontal || left == Tile::SouthEast || left == Tile::NorthEast {
-            Tile::NorthWest
-        } else if right == Tile::Horizontal || right == Tile::SouthWest || right == Tile::NorthWest
-        {
-            Tile::NorthEast
-        } else {
-            Tile::Vertical
-        }
-    } else if down == Tile::Vertical || down == Tile::NorthEast || down == Tile::NorthWest {
-        if left == Tile::Horizontal || left == Tile::SouthEast || left == Tile::NorthEast {
-            Tile::SouthWest
-        } else {
-            Tile::SouthEast
-        }
-    } else {
-        Tile::Horizontal
+    let start = match starts[..] {
+        [] => return Err(MazeError::NoStart),
+        [start] => start,
+        _ => return Err(MazeError::ManyStarts(starts)),
     };
+    infer_start(start, &mut grid)?;
 
-    grid[r][c] = starting_tile;
+    Ok((start, grid))
+}
 
-    ((r, c), grid)
+fn print_network(network: &PipeNetwork, summary: bool, queries: &[(String, (usize, usize))]) {
+    let show = |(r, c): (usize, usize)| format!("row {} col {}", r - 1, c - 1);
+
+    if summary {
+        println!("Loops: {}", network.loops.len());
+        for (i, pipe_loop) in network.loops.iter().enumerate() {
+            println!(
+                "  Loop {}: length {}, area {}, {} interior tiles, through {}",
+                i + 1,
+                pipe_loop.tiles.len(),
+                pipe_loop.area,
+                pipe_loop.interior,
+                show(pipe_loop.tiles[0])
+            );
+        }
+        println!(
+            "Dangling segments: {} ({} tiles)",
+            network.dangling.len(),
+            network.dangling.iter().map(|seg| seg.len()).sum::<usize>()
+        );
+    }
+
+    let (num_rows, num_cols) = network.size();
+    for (query, pos) in queries {
+        // the padding around the grid isn't part of the maze either
+        if pos.0 >= num_rows - 1 || pos.1 >= num_cols - 1 {
+            println!("{} is outside the maze", show(*pos));
+            continue;
+        }
+        match query.as_str() {
+            "--farthest" => match network.farthest(*pos) {
+                Some((far, dist)) => println!(
+                    "Farthest from {}: {} ({} steps along the loop)",
+                    show(*pos),
+                    show(far),
+                    dist
+                ),
+                None => println!("{} is not on a loop", show(*pos)),
+            },
+            _ => match network.enclosing(*pos) {
+                Some(idx) => println!("{} is enclosed by loop {}", show(*pos), idx + 1),
+                None => println!("{} is not enclosed by any loop", show(*pos)),
+            },
+        }
+    }
 }
 
 fn main() {
@@ -136,15 +438,72 @@ fn main() {
 
     let now = Instant::now();
 
-    let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let (start_pos, grid) = parse_input(&raw_input);
+    // --render draws the maze in the terminal, --svg PATH writes it as an svg.
+    // --network lists every loop and dangling segment, and --farthest R C and
+    // --enclosing R C query the network about a tile
+    let mut show = false;
+    let mut svg_path = None;
+    let mut network = false;
+    let mut queries = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--render" => show = true,
+            "--svg" => svg_path = Some(args.next().expect("--svg needs a path")),
+            "--network" => network = true,
+            "--farthest" | "--enclosing" => {
+                let mut coord = || {
+                    args.next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .unwrap_or_else(|| panic!("{} needs a row and column", flag))
+                };
+                // the grid is padded by one tile on every side
+                let pos = (coord() + 1, coord() + 1);
+                queries.push((flag.clone(), pos));
+            }
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
 
-    let main_loop = find_main_loop(start_pos, &grid);
+    let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
+    let maze = parse_input(&raw_input)
+        .and_then(|(start_pos, grid)| Ok((find_main_loop(start_pos, &grid)?, grid)));
+    let (main_loop, grid) = match maze {
+        Ok(maze) => maze,
+        Err(err) => {
+            println!("Error: {}", err);
+            return;
+        }
+    };
     let (perim, area) = measure_loop(&main_loop);
 
     let part_one = perim / 2;
     let part_two = area + 1 - part_one; // pick's theorem
 
+    let regions = classify(&grid, &main_loop);
+    let scanline_count = regions
+        .iter()
+        .flatten()
+        .filter(|&&region| region == Region::Inside)
+        .count();
+    if scanline_count != part_two {
+        println!(
+            "Warning: scanline count of {} enclosed tiles disagrees with pick's theorem",
+            scanline_count
+        );
+    }
+
+    if show {
+        print!("{}", render(&grid, &regions));
+    }
+    if let Some(path) = svg_path {
+        fs::write(&path, render_svg(&grid, &regions)).expect("Could not write svg");
+    }
+
+    if network || !queries.is_empty() {
+        print_network(&PipeNetwork::new(&grid), network, &queries);
+    }
+
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
 
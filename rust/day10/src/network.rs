@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use crate::{measure_loop, Tile, DELTAS};
+
+pub struct PipeLoop {
+    pub tiles: Vec<(usize, usize)>,
+    pub area: usize,
+    pub interior: usize,
+}
+
+// every pipe in the grid, split into closed loops and dangling segments. two tiles are
+// joined when each connects towards the other
+pub struct PipeNetwork {
+    pub loops: Vec<PipeLoop>,
+    pub dangling: Vec<Vec<(usize, usize)>>,
+    on_loop: Vec<Vec<Option<usize>>>,
+    grid: Vec<Vec<Tile>>,
+}
+
+impl PipeNetwork {
+    pub fn new(grid: &[Vec<Tile>]) -> Self {
+        let mut seen = vec![vec![false; grid[0].len()]; grid.len()];
+        let mut on_loop = vec![vec![None; grid[0].len()]; grid.len()];
+        let mut loops = vec![];
+        let mut dangling = vec![];
+
+        for r in 0..grid.len() {
+            for c in 0..grid[0].len() {
+                if seen[r][c] || grid[r][c] == Tile::Ground {
+                    continue;
+                }
+
+                let mut component = vec![];
+                let mut closed = true;
+                let mut queue = VecDeque::from([(r, c)]);
+                seen[r][c] = true;
+                while let Some(pos) = queue.pop_front() {
+                    component.push(pos);
+                    let neighbours = Self::neighbours(grid, pos);
+                    closed &= neighbours.len() == 2;
+                    for next in neighbours {
+                        if !seen[next.0][next.1] {
+                            seen[next.0][next.1] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+
+                if closed {
+                    let tiles = Self::walk(grid, (r, c));
+                    let (perim, area) = measure_loop(&tiles);
+                    for &(r, c) in tiles.iter() {
+                        on_loop[r][c] = Some(loops.len());
+                    }
+                    loops.push(PipeLoop {
+                        tiles,
+                        area,
+                        interior: area + 1 - perim / 2, // pick's theorem
+                    });
+                } else {
+                    dangling.push(component);
+                }
+            }
+        }
+
+        Self {
+            loops,
+            dangling,
+            on_loop,
+            grid: grid.to_vec(),
+        }
+    }
+
+    fn neighbours(grid: &[Vec<Tile>], (r, c): (usize, usize)) -> Vec<(usize, usize)> {
+        grid[r][c]
+            .connections()
+            .iter()
+            .enumerate()
+            .filter(|(_, &conn)| conn)
+            .filter_map(|(dir, _)| {
+                let (d_r, d_c) = DELTAS[dir];
+                let (n_r, n_c) = (r as isize + d_r, c as isize + d_c);
+                if n_r < 0 || n_c < 0 || n_r as usize >= grid.len() || n_c as usize >= grid[0].len()
+                {
+                    return None;
+                }
+                let (n_r, n_c) = (n_r as usize, n_c as usize);
+                grid[n_r][n_c].connections()[(dir + 2) % 4].then_some((n_r, n_c))
+            })
+            .collect()
+    }
+
+    // the tiles of a closed loop in order, starting from start
+    fn walk(grid: &[Vec<Tile>], start: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut tiles = vec![start];
+        let mut prev = start;
+        let mut pos = Self::neighbours(grid, start)[0];
+        while pos != start {
+            tiles.push(pos);
+            let next = Self::neighbours(grid, pos)
+                .into_iter()
+                .find(|&n| n != prev)
+                .unwrap();
+            (prev, pos) = (pos, next);
+        }
+        tiles
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.grid.len(), self.grid[0].len())
+    }
+
+    // the loop tile farthest along the loop from pos, and how many steps away it is
+    pub fn farthest(&self, pos: (usize, usize)) -> Option<((usize, usize), usize)> {
+        let pipe_loop = &self.loops[self.on_loop[pos.0][pos.1]?];
+        let len = pipe_loop.tiles.len();
+        let i = pipe_loop.tiles.iter().position(|&t| t == pos)?;
+
+        Some((pipe_loop.tiles[(i + len / 2) % len], len / 2))
+    }
+
+    // counts crossings of the loop to the left of pos, as with the scanline classification
+    fn inside(&self, idx: usize, (r, c): (usize, usize)) -> bool {
+        let crossings = (0..c)
+            .filter(|&c2| self.on_loop[r][c2] == Some(idx) && self.grid[r][c2].connections()[0])
+            .count();
+        crossings % 2 == 1
+    }
+
+    // the innermost loop that has pos strictly inside it
+    pub fn enclosing(&self, pos: (usize, usize)) -> Option<usize> {
+        (0..self.loops.len())
+            .filter(|&idx| self.on_loop[pos.0][pos.1] != Some(idx))
+            .filter(|&idx| self.inside(idx, pos))
+            .min_by_key(|&idx| self.loops[idx].area)
+    }
+}
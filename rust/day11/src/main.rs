@@ -1,3 +1,4 @@
+use std::env;
 use std::fs;
 use std::time::Instant;
 
@@ -31,13 +32,7 @@ impl Data {
         let galaxies = (0..num_rows)
             .flat_map(|r| {
                 (0..num_cols)
-                    .filter_map(|c| {
-                        if field[r][c].clone() {
-                            Some((r, c))
-                        } else {
-                            None
-                        }
-                    })
+                    .filter_map(|c| if field[r][c] { Some((r, c)) } else { None })
                     .collect::<Vec<_>>()
             })
             .collect::<Vec<_>>();
@@ -49,34 +44,289 @@ impl Data {
         }
     }
 
-    fn get_distances(&self, expansion: usize) -> usize {
-        self.galaxies
+    // uniform expansion: each void row becomes row_factor rows, each void column col_factor
+    fn expansion(&self, row_factor: u64, col_factor: u64) -> Expansion {
+        Expansion {
+            row_weights: vec![row_factor; self.row_voids.len()],
+            col_weights: vec![col_factor; self.col_voids.len()],
+        }
+    }
+
+    // position of every row (or column) once the voids have expanded, as a prefix sum
+    // of line widths
+    fn expanded_positions(voids: &[bool], weights: &[u64]) -> Vec<u64> {
+        voids
             .iter()
-            .enumerate()
-            .map(|(i, g_i)| {
-                let &(r_i, c_i) = g_i;
-                self.galaxies
-                    .iter()
-                    .skip(i)
-                    .map(|g_j| {
-                        let &(r_j, c_j) = g_j;
-
-                        let (r_min, r_max) = if r_i < r_j { (r_i, r_j) } else { (r_j, r_i) };
-                        let (c_min, c_max) = if c_i < c_j { (c_i, c_j) } else { (c_j, c_i) };
-
-                        let d_r = (r_max - r_min)
-                            + (expansion - 1)
-                                * (r_min..=r_max).filter(|&rr| self.row_voids[rr]).count();
-                        let d_c = (c_max - c_min)
-                            + (expansion - 1)
-                                * (c_min..=c_max).filter(|&cc| self.col_voids[cc]).count();
-
-                        d_r + d_c
-                    })
-                    .sum::<usize>()
+            .zip(weights.iter())
+            .scan(0, |pos, (&void, &weight)| {
+                let this = *pos;
+                *pos += if void { weight } else { 1 };
+                Some(this)
             })
+            .collect()
+    }
+
+    // galaxy coordinates in expanded space
+    fn expanded(&self, expansion: &Expansion) -> Vec<(u64, u64)> {
+        let row_pos = Self::expanded_positions(&self.row_voids, &expansion.row_weights);
+        let col_pos = Self::expanded_positions(&self.col_voids, &expansion.col_weights);
+
+        self.galaxies
+            .iter()
+            .map(|&(r, c)| (row_pos[r], col_pos[c]))
+            .collect()
+    }
+
+    fn get_distances(&self, expansion: &Expansion) -> u128 {
+        let expanded = self.expanded(expansion);
+
+        // manhattan distance splits by axis, so each axis is summed on its own
+        let rows = expanded.iter().map(|&(r, _)| r).collect();
+        let cols = expanded.iter().map(|&(_, c)| c).collect();
+
+        pairwise_sum(rows) + pairwise_sum(cols)
+    }
+
+    // max(|dr|, |dc|) is half of |du| + |dv| for u = r + c and v = r - c, so chebyshev
+    // distance sums the same way once rotated. v is shifted to stay positive
+    fn get_chebyshev_distances(&self, expansion: &Expansion) -> u128 {
+        let expanded = self.expanded(expansion);
+        let shift = expanded.iter().map(|&(_, c)| c).max().unwrap_or(0);
+
+        let us = expanded.iter().map(|&(r, c)| r + c).collect();
+        let vs = expanded.iter().map(|&(r, c)| r + shift - c).collect();
+
+        (pairwise_sum(us) + pairwise_sum(vs)) / 2
+    }
+
+    // a galaxy given either by its number (counting from 1 in reading order, as the
+    // puzzle does) or by its coordinate "r,c" in the unexpanded image
+    fn find_galaxy(&self, spec: &str) -> Result<usize, String> {
+        if let Some((r, c)) = spec.split_once(',') {
+            let pos = (
+                r.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Bad row in {}", spec))?,
+                c.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Bad column in {}", spec))?,
+            );
+            self.galaxies
+                .iter()
+                .position(|&g| g == pos)
+                .ok_or(format!("No galaxy at {}", spec))
+        } else {
+            spec.parse::<usize>()
+                .ok()
+                .filter(|&n| n >= 1 && n <= self.galaxies.len())
+                .map(|n| n - 1)
+                .ok_or(format!("No galaxy number {}", spec))
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Metric {
+    Manhattan,
+    Chebyshev,
+    Euclidean,
+}
+
+impl Metric {
+    fn distance(&self, (r1, c1): (u64, u64), (r2, c2): (u64, u64)) -> f64 {
+        let (d_r, d_c) = (r1.abs_diff(r2) as f64, c1.abs_diff(c2) as f64);
+        match self {
+            Metric::Manhattan => d_r + d_c,
+            Metric::Chebyshev => d_r.max(d_c),
+            Metric::Euclidean => d_r.hypot(d_c),
+        }
+    }
+
+    fn show(&self, dist: f64) -> String {
+        match self {
+            Metric::Euclidean => format!("{:.3}", dist),
+            _ => format!("{}", dist),
+        }
+    }
+}
+
+// distance queries between individual galaxies in expanded space
+struct Galaxies {
+    positions: Vec<(u64, u64)>,
+    metric: Metric,
+}
+
+impl Galaxies {
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        self.metric.distance(self.positions[a], self.positions[b])
+    }
+
+    // nearest and farthest other galaxy, each with its distance
+    fn neighbours(&self, a: usize) -> Option<((usize, f64), (usize, f64))> {
+        let others = (0..self.positions.len())
+            .filter(|&b| b != a)
+            .map(|b| (b, self.distance(a, b)));
+        let nearest = others.clone().min_by(|x, y| x.1.total_cmp(&y.1))?;
+        let farthest = others.max_by(|x, y| x.1.total_cmp(&y.1))?;
+
+        Some((nearest, farthest))
+    }
+
+    // sum over every pair of galaxies
+    fn total(&self) -> f64 {
+        (0..self.positions.len())
+            .flat_map(|a| (a + 1..self.positions.len()).map(move |b| (a, b)))
+            .map(|(a, b)| self.distance(a, b))
             .sum()
     }
+
+    fn matrix(&self) -> Vec<Vec<f64>> {
+        (0..self.positions.len())
+            .map(|a| {
+                (0..self.positions.len())
+                    .map(|b| self.distance(a, b))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// the width that each void line expands to, indexed by row or column. entries for lines
+// that aren't void are ignored
+struct Expansion {
+    row_weights: Vec<u64>,
+    col_weights: Vec<u64>,
+}
+
+// sum of |x_i - x_j| over all pairs. once sorted, the k-th value is larger than the k
+// before it and smaller than the n - k - 1 after it
+fn pairwise_sum(mut xs: Vec<u64>) -> u128 {
+    xs.sort_unstable();
+    let n = xs.len() as i128;
+    xs.iter()
+        .enumerate()
+        .map(|(k, &x)| x as i128 * (2 * k as i128 - n + 1))
+        .sum::<i128>() as u128
+}
+
+enum Query {
+    Distance(String, String),
+    Neighbours(String),
+    Matrix,
+}
+
+fn parse_args(args: &[String], data: &Data) -> Result<(Expansion, Metric, Vec<Query>), String> {
+    let mut factors = (2, 2);
+    let mut overrides = vec![];
+    let mut metric = Metric::Manhattan;
+    let mut queries = vec![];
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--matrix" {
+            queries.push(Query::Matrix);
+            continue;
+        }
+        let val = args.next().ok_or(format!("Missing value for {}", flag))?;
+        let num = |s: &str| {
+            s.parse::<u64>()
+                .map_err(|_| format!("Bad value {} for {}", s, flag))
+        };
+        match flag.as_str() {
+            "--rows" => factors.0 = num(val)?,
+            "--cols" => factors.1 = num(val)?,
+            "--row-weight" | "--col-weight" => {
+                let (line, weight) = val
+                    .split_once('=')
+                    .ok_or(format!("Expected LINE=WEIGHT for {}", flag))?;
+                let line = num(line)? as usize;
+                overrides.push((flag == "--row-weight", line, num(weight)?));
+            }
+            "--metric" => {
+                metric = match val.as_str() {
+                    "manhattan" => Metric::Manhattan,
+                    "chebyshev" => Metric::Chebyshev,
+                    "euclidean" => Metric::Euclidean,
+                    _ => return Err(format!("Unknown metric {}", val)),
+                }
+            }
+            "--distance" => {
+                let other = args.next().ok_or("--distance needs two galaxies")?;
+                queries.push(Query::Distance(val.clone(), other.clone()));
+            }
+            "--neighbours" => queries.push(Query::Neighbours(val.clone())),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    let mut expansion = data.expansion(factors.0, factors.1);
+    for (is_row, line, weight) in overrides {
+        let (weights, voids) = if is_row {
+            (&mut expansion.row_weights, &data.row_voids)
+        } else {
+            (&mut expansion.col_weights, &data.col_voids)
+        };
+        if !voids.get(line).copied().unwrap_or(false) {
+            return Err(format!("Line {} is not a void", line));
+        }
+        weights[line] = weight;
+    }
+
+    Ok((expansion, metric, queries))
+}
+
+fn run_queries(data: &Data, galaxies: &Galaxies, queries: &[Query]) -> Result<(), String> {
+    let metric = galaxies.metric;
+    let name = |a: usize| {
+        format!(
+            "galaxy {} ({},{})",
+            a + 1,
+            data.galaxies[a].0,
+            data.galaxies[a].1
+        )
+    };
+
+    for query in queries {
+        match query {
+            Query::Distance(a, b) => {
+                let (a, b) = (data.find_galaxy(a)?, data.find_galaxy(b)?);
+                println!(
+                    "Distance from {} to {}: {}",
+                    name(a),
+                    name(b),
+                    metric.show(galaxies.distance(a, b))
+                );
+            }
+            Query::Neighbours(spec) => {
+                let targets = if spec == "all" {
+                    (0..data.galaxies.len()).collect()
+                } else {
+                    vec![data.find_galaxy(spec)?]
+                };
+                for a in targets {
+                    match galaxies.neighbours(a) {
+                        Some(((near, near_d), (far, far_d))) => println!(
+                            "{}: nearest {} at {}, farthest {} at {}",
+                            name(a),
+                            name(near),
+                            metric.show(near_d),
+                            name(far),
+                            metric.show(far_d)
+                        ),
+                        None => println!("{} has no other galaxies", name(a)),
+                    }
+                }
+            }
+            Query::Matrix => {
+                for row in galaxies.matrix() {
+                    let row = row.iter().map(|&d| metric.show(d)).collect::<Vec<_>>();
+                    println!("{}", row.join("\t"));
+                }
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn main() {
@@ -88,8 +338,36 @@ fn main() {
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
     let data = Data::from_str(&raw_input);
 
-    let part_one = data.get_distances(2);
-    let part_two = data.get_distances(1000000);
+    // --rows F and --cols F set the expansion of void rows and columns, and
+    // --row-weight R=W and --col-weight C=W override the width of a single void line.
+    // galaxies (a number or "r,c") can be queried with --distance A B, --neighbours A
+    // (or "all") and --matrix, measured with --metric manhattan, chebyshev or euclidean
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        let (expansion, metric, queries) =
+            parse_args(&args, &data).unwrap_or_else(|err| panic!("{}", err));
+        let galaxies = Galaxies {
+            positions: data.expanded(&expansion),
+            metric,
+        };
+        if queries.is_empty() {
+            // the integer metrics are summed exactly, a pair at a time is only needed for
+            // euclidean distance
+            let total = match metric {
+                Metric::Manhattan => data.get_distances(&expansion).to_string(),
+                Metric::Chebyshev => data.get_chebyshev_distances(&expansion).to_string(),
+                Metric::Euclidean => metric.show(galaxies.total()),
+            };
+            println!("Total distance: {}", total);
+        } else {
+            run_queries(&data, &galaxies, &queries).unwrap_or_else(|err| println!("{}", err));
+        }
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
+
+    let part_one = data.get_distances(&data.expansion(2, 2));
+    let part_two = data.get_distances(&data.expansion(1000000, 1000000));
 
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
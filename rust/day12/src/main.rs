@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::fs;
 use std::time::Instant;
 
@@ -11,146 +12,325 @@ enum Status {
     Unknown,
 }
 
-fn count_arrangements(records: &Vec<(Vec<Status>, Vec<usize>)>, multiplier: usize) -> usize {
+type Record = (Vec<Status>, Vec<usize>);
+
+// repeats a record factor times, with a separator spring between the copies
+fn unfold(
+    statuses: &[Status],
+    pattern: &[usize],
+    factor: usize,
+    separator: Status,
+) -> Result<Record, String> {
+    if factor == 0 {
+        return Err(String::from("Unfold factor must be at least 1"));
+    }
+
+    let mut mult_statuses = vec![];
+    let mut mult_pattern = vec![];
+    for i in 0..factor {
+        mult_statuses.extend(statuses.iter().copied());
+        if i < factor - 1 {
+            mult_statuses.push(separator);
+        }
+        mult_pattern.extend(pattern.iter().copied());
+    }
+
+    Ok((mult_statuses, mult_pattern))
+}
+
+fn count_arrangements(
+    records: &[Record],
+    multiplier: usize,
+    separator: Status,
+) -> Result<BigCount, String> {
+    let mut counter = ArrangementCounter::default();
+
     records
         .iter()
-        .map(|(statuses, pattern)| {
-            let mut mult_statuses = vec![];
-            let mut mult_pattern = vec![];
-
-            for i in 0..multiplier {
-                for j in 0..statuses.len() {
-                    mult_statuses.push(statuses[j]);
-                }
-                if i < multiplier - 1 {
-                    mult_statuses.push(Status::Unknown);
-                }
-                for j in 0..pattern.len() {
-                    mult_pattern.push(pattern[j]);
-                }
-            }
-            count_arrangements_for_record(&mult_statuses, &mult_pattern)
+        .try_fold(BigCount::default(), |mut total, (statuses, pattern)| {
+            let (mult_statuses, mult_pattern) = unfold(statuses, pattern, multiplier, separator)?;
+            total.add(&counter.count(&mult_statuses, &mult_pattern));
+            Ok(total)
         })
-        .sum()
 }
 
-fn count_arrangements_for_record(statuses: &Vec<Status>, pattern: &Vec<usize>) -> usize {
-    let mut starts = HashMap::<usize, usize>::new();
-    starts.insert(0, 1);
+// an arbitrary precision count, which is all the dp needs: it only ever adds. stored as
+// little endian 64 bit limbs
+#[derive(Clone, Default)]
+struct BigCount(Vec<u64>);
 
-    for i in 0..pattern.len() {
-        let rest_pattern = pattern[i..].to_vec();
-        let rest_needed = rest_pattern.iter().sum::<usize>() + rest_pattern.len() - 1;
-        let mut new_starts = HashMap::<usize, usize>::new();
+impl BigCount {
+    fn one() -> Self {
+        Self(vec![1])
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(|&limb| limb == 0)
+    }
 
-        for (&start, &count) in starts.iter() {
-            if statuses.len() - start < rest_needed {
-                continue;
+    fn add(&mut self, other: &Self) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        let mut carry = false;
+        for i in 0..self.0.len() {
+            let (sum, c1) = self.0[i].overflowing_add(other.0.get(i).copied().unwrap_or(0));
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            self.0[i] = sum;
+            carry = c1 || c2;
+            if !carry && i >= other.0.len() {
+                break;
             }
+        }
+        if carry {
+            self.0.push(1);
+        }
+    }
+}
 
-            let matches = match_next_status(&statuses, &pattern, start, i);
-            for (last_in_seq, ct) in matches.iter() {
-                let next_start = last_in_seq + 1;
-                let prev = new_starts.get(&next_start).unwrap_or(&0);
-                new_starts.insert(next_start, prev + count * ct);
+impl fmt::Display for BigCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // peel off 19 decimal digits at a time, the most that fits in a u64
+        const CHUNK: u128 = 10_000_000_000_000_000_000;
+        let mut limbs = self.0.clone();
+        let mut chunks = vec![];
+        while limbs.iter().any(|&limb| limb != 0) {
+            let mut rem = 0u128;
+            for limb in limbs.iter_mut().rev() {
+                let cur = (rem << 64) | *limb as u128;
+                *limb = (cur / CHUNK) as u64;
+                rem = cur % CHUNK;
             }
+            chunks.push(rem as u64);
         }
 
-        starts = new_starts;
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|chunk| write!(f, "{:019}", chunk))
+            }
+        }
     }
+}
 
-    starts
-        .into_iter()
-        .filter_map(|(start, ct)| {
-            if start > statuses.len() - 1 {
-                Some(ct)
-            } else if statuses[start..].iter().any(|&s| s == Status::Damaged) {
-                None
-            } else {
-                Some(ct)
-            }
-        })
-        .sum()
+// counts arrangements with a dp over (groups placed, position), one row of groups at a
+// time. the record gets an extra operational spring on the end so that every group is
+// followed by a gap, and the buffers are kept between records
+#[derive(Default)]
+struct ArrangementCounter {
+    springs: Vec<Status>,
+    operational_before: Vec<usize>,
+    current: Vec<BigCount>,
+    next: Vec<BigCount>,
 }
 
-fn match_next_status(
-    statuses: &Vec<Status>,
-    pattern: &Vec<usize>,
-    status_start: usize,
-    next_match_idx: usize,
-) -> HashMap<usize, usize> {
-    let last = next_match_idx == pattern.len() - 1;
-    let mut results = HashMap::new();
+impl ArrangementCounter {
+    fn prepare(&mut self, statuses: &[Status]) {
+        self.springs.clear();
+        self.springs.extend_from_slice(statuses);
+        self.springs.push(Status::Operational);
 
-    let needed_for_rest =
-        pattern[(next_match_idx + 1)..].iter().sum::<usize>() + pattern.len() - next_match_idx - 1;
-    let end = statuses.len() - needed_for_rest;
-    let to_match = pattern[next_match_idx];
+        self.operational_before.clear();
+        self.operational_before.push(0);
+        for (i, &s) in self.springs.iter().enumerate() {
+            self.operational_before
+                .push(self.operational_before[i] + (s == Status::Operational) as usize);
+        }
+    }
 
-    let mut to_check = vec![status_start];
+    // whether a group of length len can start at i and be followed by a gap
+    fn fits(&self, i: usize, len: usize) -> bool {
+        i + len < self.springs.len()
+            && self.operational_before[i + len] == self.operational_before[i]
+            && self.springs[i + len] != Status::Damaged
+    }
 
-    while let Some(next) = to_check.pop() {
-        if end - next < to_match {
-            continue;
+    fn count(&mut self, statuses: &[Status], pattern: &[usize]) -> BigCount {
+        self.prepare(statuses);
+        let end = self.springs.len();
+
+        self.current.clear();
+        self.current.resize(end + 1, BigCount::default());
+        self.current[0] = BigCount::one();
+        self.carry_gaps();
+
+        for &len in pattern {
+            self.next.clear();
+            self.next.resize(end + 1, BigCount::default());
+            for i in 0..end {
+                if !self.current[i].is_zero() && self.fits(i, len) {
+                    self.next[i + len + 1].add(&self.current[i]);
+                }
+            }
+            std::mem::swap(&mut self.current, &mut self.next);
+            self.carry_gaps();
         }
 
-        if statuses[next] == Status::Operational {
-            to_check.push(next + 1);
-            continue;
+        self.current.swap_remove(end)
+    }
+
+    // extends every partial arrangement over operational (or unknown) springs
+    fn carry_gaps(&mut self) {
+        for i in 0..self.springs.len() {
+            if self.springs[i] != Status::Damaged {
+                let (done, rest) = self.current.split_at_mut(i + 1);
+                rest[0].add(&done[i]);
+            }
         }
+    }
+
+    // which (groups placed, position) states can still be completed, so that enumeration
+    // never wanders down a dead end
+    fn completable(&mut self, statuses: &[Status], pattern: &[usize]) -> Vec<Vec<bool>> {
+        self.prepare(statuses);
+        let end = self.springs.len();
 
-        if statuses[next] == Status::Unknown {
-            to_check.push(next + 1);
+        let mut ok = vec![vec![false; end + 1]; pattern.len() + 1];
+        ok[pattern.len()][end] = true;
+        for j in (0..=pattern.len()).rev() {
+            for i in (0..end).rev() {
+                let gap = self.springs[i] != Status::Damaged && ok[j][i + 1];
+                let group =
+                    j < pattern.len() && self.fits(i, pattern[j]) && ok[j + 1][i + pattern[j] + 1];
+                ok[j][i] = gap || group;
+            }
         }
+        ok
+    }
+}
 
-        if (0..to_match)
-            .all(|i| statuses[next + i] == Status::Damaged || statuses[next + i] == Status::Unknown)
-        {
-            let last_in_run = next + to_match - 1;
+// lazily lists every arrangement of a record as a string of '#' and '.'
+struct Arrangements {
+    springs: Vec<Status>,
+    pattern: Vec<usize>,
+    completable: Vec<Vec<bool>>,
+    // (groups placed, position, arrangement so far)
+    stack: Vec<(usize, usize, String)>,
+}
 
-            if last_in_run == statuses.len() - 1 {
-                if last {
-                    let prev = results.get(&last_in_run).unwrap_or(&0);
-                    results.insert(last_in_run, prev + 1);
-                }
-                continue;
+impl Arrangements {
+    fn new(statuses: &[Status], pattern: &[usize]) -> Self {
+        let mut counter = ArrangementCounter::default();
+        let completable = counter.completable(statuses, pattern);
+        let stack = if completable[0][0] {
+            vec![(0, 0, String::new())]
+        } else {
+            vec![]
+        };
+
+        Self {
+            springs: counter.springs,
+            pattern: pattern.to_vec(),
+            completable,
+            stack,
+        }
+    }
+}
+
+impl Iterator for Arrangements {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let end = self.springs.len();
+        while let Some((j, i, arrangement)) = self.stack.pop() {
+            if i == end {
+                return Some(arrangement);
             }
 
-            if statuses[last_in_run + 1] == Status::Damaged {
-                continue;
+            // pushed in reverse so that groups are placed as early as possible first
+            if self.springs[i] != Status::Damaged && self.completable[j][i + 1] {
+                let mut gap = arrangement.clone();
+                if i < end - 1 {
+                    gap.push('.');
+                }
+                self.stack.push((j, i + 1, gap));
             }
+            if j < self.pattern.len() {
+                let len = self.pattern[j];
+                let after = i + len + 1;
+                let fits = i + len < end
+                    && self.springs[i..i + len]
+                        .iter()
+                        .all(|&s| s != Status::Operational)
+                    && self.springs[i + len] != Status::Damaged;
+                if fits && self.completable[j + 1][after] {
+                    let mut group = arrangement;
+                    group.extend((0..len).map(|_| '#'));
+                    if i + len < end - 1 {
+                        group.push('.');
+                    }
+                    self.stack.push((j + 1, after, group));
+                }
+            }
+        }
+
+        None
+    }
+}
 
-            let prev = results.get(&(last_in_run + 1)).unwrap_or(&0);
-            results.insert(last_in_run + 1, prev + 1);
+// strict parsing rejects anything but '#', '?' and '.' in the springs, and groups of
+// length zero. lenient parsing treats any other character as an operational spring, as
+// the puzzle input never has one. errors are reported for every line, not just the first
+fn parse_input(input: &str, lenient: bool) -> Result<Vec<Record>, String> {
+    let mut records = vec![];
+    let mut errors = vec![];
+
+    for (n, line) in input.trim().lines().enumerate() {
+        match parse_record(line, lenient) {
+            Ok(record) => records.push(record),
+            Err(err) => errors.push(format!("Line {}: {}", n + 1, err)),
         }
     }
 
-    results
+    if errors.is_empty() {
+        Ok(records)
+    } else {
+        Err(errors.join("\n"))
+    }
 }
 
-fn parse_input(input: &str) -> Vec<(Vec<Status>, Vec<usize>)> {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            let pcs = line.trim().split_whitespace().collect::<Vec<_>>();
-            let statuses = pcs[0]
-                .chars()
-                .map(|c| match c {
-                    '#' => Status::Damaged,
-                    '?' => Status::Unknown,
-                    _ => Status::Operational,
-                })
-                .collect::<Vec<_>>();
-
-            let pattern = pcs[1]
-                .split(',')
-                .map(|n| n.parse::<usize>().unwrap())
-                .collect::<Vec<_>>();
-
-            (statuses, pattern)
+fn parse_record(line: &str, lenient: bool) -> Result<Record, String> {
+    let (springs, groups) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [springs, groups] => (springs, groups),
+        [_] => return Err(String::from("missing group list")),
+        _ => return Err(format!("expected springs and groups, got {:?}", line)),
+    };
+
+    let statuses = springs
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            '#' => Ok(Status::Damaged),
+            '?' => Ok(Status::Unknown),
+            '.' => Ok(Status::Operational),
+            _ if lenient => Ok(Status::Operational),
+            _ => Err(format!("unexpected character {:?} at column {}", c, i + 1)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pattern = groups
+        .split(',')
+        .enumerate()
+        .map(|(i, n)| match n.parse::<usize>() {
+            Ok(0) if !lenient => Err(format!("group {} has length zero", i + 1)),
+            Ok(len) => Ok(len),
+            Err(_) if n.is_empty() => Err(format!("group {} is empty", i + 1)),
+            Err(_) => Err(format!("group {} is not a number: {:?}", i + 1, n)),
         })
-        .collect::<Vec<_>>()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((statuses, pattern))
+}
+
+// records that can't be arranged at all, which usually means the record is wrong
+fn impossible_records(records: &[Record]) -> Vec<usize> {
+    let mut counter = ArrangementCounter::default();
+    (0..records.len())
+        .filter(|&i| counter.count(&records[i].0, &records[i].1).is_zero())
+        .collect()
 }
 
 fn main() {
@@ -160,13 +340,87 @@ fn main() {
     let now = Instant::now();
 
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let records = parse_input(&raw_input);
 
-    let part_one = count_arrangements(&records, 1);
-    let part_two = count_arrangements(&records, 5);
+    // --enumerate N lists the arrangements of record N (counting from 1), at most
+    // --limit of them. --unfold N and --separator C change how records are unfolded.
+    // --lenient accepts any character as an operational spring and skips validation
+    let mut enumerate = None;
+    let mut limit = usize::MAX;
+    let mut unfolding = None;
+    let mut separator = Status::Unknown;
+    let mut lenient = false;
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--lenient" {
+            lenient = true;
+            continue;
+        }
+        let val = args
+            .next()
+            .unwrap_or_else(|| panic!("{} needs a value", flag));
+        let num = || {
+            val.parse::<usize>()
+                .unwrap_or_else(|_| panic!("{} needs a number", flag))
+        };
+        match flag.as_str() {
+            "--enumerate" => enumerate = Some(num()),
+            "--limit" => limit = num(),
+            "--unfold" => unfolding = Some(num()),
+            "--separator" => {
+                separator = match val.as_str() {
+                    "#" => Status::Damaged,
+                    "?" => Status::Unknown,
+                    "." => Status::Operational,
+                    _ => panic!("Separator must be one of # ? ."),
+                }
+            }
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
 
-    println!("Part one: {}", part_one);
-    println!("Part two: {}", part_two);
+    let records = match parse_input(&raw_input, lenient) {
+        Ok(records) => records,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    if !lenient {
+        let impossible = impossible_records(&records);
+        if !impossible.is_empty() {
+            for i in impossible {
+                println!("Line {}: record has no valid arrangements", i + 1);
+            }
+            return;
+        }
+    }
+
+    if let Some(n) = enumerate {
+        let (statuses, pattern) = records
+            .get(n.wrapping_sub(1))
+            .unwrap_or_else(|| panic!("No record {}", n));
+        match unfold(statuses, pattern, unfolding.unwrap_or(1), separator) {
+            Ok((statuses, pattern)) => {
+                for arrangement in Arrangements::new(&statuses, &pattern).take(limit) {
+                    println!("{}", arrangement);
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+
+    let show =
+        |count: Result<BigCount, String>| count.map(|c| c.to_string()).unwrap_or_else(|err| err);
+    if let Some(factor) = unfolding {
+        let count = count_arrangements(&records, factor, separator);
+        println!("Arrangements: {}", show(count));
+    } else {
+        let part_one = count_arrangements(&records, 1, separator);
+        let part_two = count_arrangements(&records, 5, separator);
+
+        println!("Part one: {}", show(part_one));
+        println!("Part two: {}", show(part_two));
+    }
 
     println!("Elasped time: {}ms", now.elapsed().as_millis());
 }
@@ -1,63 +1,199 @@
+use std::env;
+use std::fmt;
 use std::fs;
 use std::time::Instant;
 
 const FILEPATH: &str = "./input/input.txt";
 
-fn summarize_grids(grids: &Vec<Vec<Vec<bool>>>, smudge: bool) -> usize {
-    grids.iter().map(|grid| summarize_grid(grid, smudge)).sum()
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Axis {
+    Horizontal,
+    Vertical,
 }
 
-fn summarize_grid(grid: &Vec<Vec<bool>>, smudge: bool) -> usize {
-    let num_rows = grid.len();
-    let num_cols = grid[0].len();
-
-    if let Some(val) = find_mirror(grid, smudge) {
-        100 * (val + 1)
-    } else {
-        let tranpose = (0..num_cols)
-            .map(|c| (0..num_rows).map(|r| grid[r][c]).collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-        let val = find_mirror(&tranpose, smudge).unwrap();
-        val + 1
+// a line of reflection, given by how many rows (or columns) lie above (or left of) it.
+// each smudge is a pair of cells that differ but should be mirror images, so flipping
+// either one of them repairs the reflection
+struct Reflection {
+    axis: Axis,
+    line: usize,
+    smudges: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl Reflection {
+    fn errors(&self) -> usize {
+        self.smudges.len()
+    }
+
+    fn summary(&self) -> usize {
+        match self.axis {
+            Axis::Horizontal => 100 * self.line,
+            Axis::Vertical => self.line,
+        }
     }
 }
 
-fn find_mirror(matrix: &Vec<Vec<bool>>, smudge: bool) -> Option<usize> {
-    let num_rows = matrix.len();
-    let num_cols = matrix[0].len();
-
-    (0..(num_rows) - 1).find(|&i| {
-        let max = (i + 1).min(num_rows - i - 1);
-        let (single_error_count, multiple_error_count) =
-            (0..max).fold((0, 0), |(sing, mult), j| {
-                let v1 = &matrix[i - j];
-                let v2 = &matrix[i + j + 1];
-                let errors = (0..num_cols).filter(|&k| v1[k] != v2[k]).count();
-                if errors == 0 {
-                    (sing, mult)
-                } else if errors == 1 {
-                    (sing + 1, mult)
-                } else {
-                    (sing, mult + 1)
-                }
-            });
-        if smudge {
-            multiple_error_count == 0 && single_error_count == 1
-        } else {
-            multiple_error_count == 0 && single_error_count == 0
+impl fmt::Display for Reflection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, lines) = match self.axis {
+            Axis::Horizontal => ("horizontal", "rows"),
+            Axis::Vertical => ("vertical", "columns"),
+        };
+        write!(
+            f,
+            "{} line after {} {}, {} error(s)",
+            kind,
+            self.line,
+            lines,
+            self.errors()
+        )?;
+        for ((r1, c1), (r2, c2)) in self.smudges.iter() {
+            write!(f, "; smudge ({},{}) or ({},{})", r1, c1, r2, c2)?;
+        }
+        Ok(())
+    }
+}
+
+// the summary of every grid that has a reflection with exactly smudges errors, along
+// with the (zero based) index of every grid that has none
+fn summarize_grids(grids: &[Pattern], smudges: usize) -> (usize, Vec<usize>) {
+    let mut missing = vec![];
+    let total = grids
+        .iter()
+        .enumerate()
+        .filter_map(|(i, grid)| {
+            let summary = summarize_grid(grid, smudges);
+            if summary.is_none() {
+                missing.push(i);
+            }
+            summary
+        })
+        .sum();
+
+    (total, missing)
+}
+
+// as the puzzle has it, horizontal lines win over vertical ones, and earlier lines over
+// later ones
+fn summarize_grid(grid: &Pattern, smudges: usize) -> Option<usize> {
+    find_reflections(grid, smudges)
+        .iter()
+        .find(|refl| refl.errors() == smudges)
+        .map(|refl| refl.summary())
+}
+
+// each row and each column of a grid packed into bitmasks, 64 cells to a word, so that
+// two lines differ in popcount(a ^ b) cells
+struct Pattern {
+    rows: Vec<Vec<u64>>,
+    cols: Vec<Vec<u64>>,
+}
+
+impl Pattern {
+    fn new(grid: &[Vec<bool>]) -> Self {
+        let num_rows = grid.len();
+        let num_cols = grid[0].len();
+
+        let mut rows = vec![vec![0; num_cols.div_ceil(64)]; num_rows];
+        let mut cols = vec![vec![0; num_rows.div_ceil(64)]; num_cols];
+        for (r, row) in grid.iter().enumerate() {
+            for (c, _) in row.iter().enumerate().filter(|(_, &cell)| cell) {
+                rows[r][c / 64] |= 1 << (c % 64);
+                cols[c][r / 64] |= 1 << (r % 64);
+            }
         }
+
+        Self { rows, cols }
+    }
+}
+
+// every horizontal line and then every vertical line that reflects the grid with at
+// most max_errors differences
+fn find_reflections(grid: &Pattern, max_errors: usize) -> Vec<Reflection> {
+    let horizontal = find_mirrors(&grid.rows, max_errors)
+        .into_iter()
+        .map(|(line, smudges)| Reflection {
+            axis: Axis::Horizontal,
+            line,
+            smudges,
+        });
+    let vertical = find_mirrors(&grid.cols, max_errors)
+        .into_iter()
+        .map(|(line, smudges)| Reflection {
+            axis: Axis::Vertical,
+            line,
+            // back from (column, row) to (row, column)
+            smudges: smudges
+                .into_iter()
+                .map(|((c1, r1), (c2, r2))| ((r1, c1), (r2, c2)))
+                .collect(),
+        });
+
+    horizontal.chain(vertical).collect()
+}
+
+type Mirror = (usize, Vec<((usize, usize), (usize, usize))>);
+
+// every line between rows that reflects the packed matrix with at most max_errors
+// mismatched pairs of cells, along with those pairs. a line is abandoned as soon as it
+// goes over
+fn find_mirrors(lines: &[Vec<u64>], max_errors: usize) -> Vec<Mirror> {
+    let num_lines = lines.len();
+
+    (1..num_lines)
+        .filter_map(|line| {
+            let max = line.min(num_lines - line);
+            let mut errors = 0;
+            for j in 0..max {
+                let (a, b) = (&lines[line - j - 1], &lines[line + j]);
+                errors += a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| (x ^ y).count_ones() as usize)
+                    .sum::<usize>();
+                if errors > max_errors {
+                    return None;
+                }
+            }
+
+            // only lines that survive pay for finding where their errors are
+            let smudges = (0..max)
+                .flat_map(|j| {
+                    let (r1, r2) = (line - j - 1, line + j);
+                    differences(&lines[r1], &lines[r2]).map(move |k| ((r1, k), (r2, k)))
+                })
+                .collect();
+            Some((line, smudges))
+        })
+        .collect()
+}
+
+// the positions of the bits where two masks differ, in order
+fn differences<'a>(a: &'a [u64], b: &'a [u64]) -> impl Iterator<Item = usize> + 'a {
+    a.iter().zip(b.iter()).enumerate().flat_map(|(w, (x, y))| {
+        let mut diff = x ^ y;
+        std::iter::from_fn(move || {
+            if diff == 0 {
+                return None;
+            }
+            let bit = diff.trailing_zeros() as usize;
+            diff &= diff - 1;
+            Some(64 * w + bit)
+        })
     })
 }
 
-fn parse_input(input: &str) -> Vec<Vec<Vec<bool>>> {
+fn parse_input(input: &str) -> Vec<Pattern> {
     input
         .trim()
         .split("\n\n")
         .map(|chk| {
-            chk.trim()
+            let grid = chk
+                .trim()
                 .lines()
                 .map(|line| line.chars().map(|c| c == '#').collect::<Vec<_>>())
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            Pattern::new(&grid)
         })
         .collect::<Vec<_>>()
 }
@@ -71,9 +207,50 @@ fn main() {
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
     let grids = parse_input(&raw_input);
 
-    let part_one = summarize_grids(&grids, false);
-    let part_two = summarize_grids(&grids, true);
+    // --smudges K lists every reflection of every grid with at most K errors, then
+    // summarizes using the reflections with exactly K
+    let mut args = env::args().skip(1);
+    if let Some(flag) = args.next() {
+        if flag != "--smudges" {
+            panic!("Unknown option {}", flag);
+        }
+        let smudges = args
+            .next()
+            .and_then(|val| val.parse::<usize>().ok())
+            .expect("--smudges needs a number");
+
+        for (i, grid) in grids.iter().enumerate() {
+            let reflections = find_reflections(grid, smudges);
+            if reflections.is_empty() {
+                println!("Grid {}: no reflection", i + 1);
+            }
+            for refl in reflections {
+                println!("Grid {}: {}", i + 1, refl);
+            }
+        }
+
+        let (summary, missing) = summarize_grids(&grids, smudges);
+        for i in missing {
+            println!(
+                "Grid {} has no reflection with {} smudge(s)",
+                i + 1,
+                smudges
+            );
+        }
+        println!("Summary: {}", summary);
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
+
+    let (part_one, missing_one) = summarize_grids(&grids, 0);
+    let (part_two, missing_two) = summarize_grids(&grids, 1);
 
+    for i in missing_one {
+        println!("Grid {} has no reflection", i + 1);
+    }
+    for i in missing_two {
+        println!("Grid {} has no smudged reflection", i + 1);
+    }
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
 
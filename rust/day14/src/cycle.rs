@@ -0,0 +1,64 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// the states visited by repeatedly applying a step function, up to the first repeat.
+// states are looked up by hash, and compared in full when hashes match, so a collision
+// can never fake a cycle
+pub struct History<T> {
+    states: Vec<T>,
+    // (prefix length, period) once a state has come round again
+    cycle: Option<(usize, usize)>,
+}
+
+impl<T: Clone + Eq + Hash> History<T> {
+    // steps from start until a state repeats or limit steps have been taken, whichever
+    // comes first
+    pub fn explore<F: FnMut(&T) -> T>(start: T, mut step: F, limit: usize) -> Self {
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut states = vec![start];
+        seen.entry(Self::hash(&states[0])).or_default().push(0);
+
+        for i in 1..=limit {
+            let state = step(&states[i - 1]);
+            let hash = Self::hash(&state);
+            let bucket = seen.entry(hash).or_default();
+            if let Some(&prev) = bucket.iter().find(|&&j| states[j] == state) {
+                return Self {
+                    states,
+                    cycle: Some((prev, i - prev)),
+                };
+            }
+            bucket.push(i);
+            states.push(state);
+        }
+
+        Self {
+            states,
+            cycle: None,
+        }
+    }
+
+    fn hash(state: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // the state after n steps, if it was reached or can be worked out from the cycle
+    pub fn state_at(&self, n: usize) -> Option<&T> {
+        if n < self.states.len() {
+            return self.states.get(n);
+        }
+        let (prefix, period) = self.cycle?;
+        self.states.get(prefix + (n - prefix) % period)
+    }
+
+    pub fn prefix(&self) -> Option<usize> {
+        self.cycle.map(|(prefix, _)| prefix)
+    }
+
+    pub fn period(&self) -> Option<usize> {
+        self.cycle.map(|(_, period)| period)
+    }
+}
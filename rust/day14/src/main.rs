@@ -1,16 +1,24 @@
-use std::collections::HashMap;
+use std::env;
 use std::fs;
-use std::time::Instant;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod cycle;
+mod visual;
+
+use cycle::History;
 
 const FILEPATH: &str = "./input/input.txt";
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Tile {
     Round,
     Cube,
     Empty,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum TiltDirection {
     North,
     South,
@@ -18,91 +26,90 @@ enum TiltDirection {
     West,
 }
 
-fn tilt(grid: &Vec<Vec<Tile>>, dir: TiltDirection) -> Vec<Vec<Tile>> {
-    let size = grid.len();
-
-    let mut grid = grid.clone();
-
-    for a in 0..size {
-        for b in 0..size {
-            let (r, c) = match dir {
-                TiltDirection::North => (b, a),
-                TiltDirection::West => (a, b),
-                TiltDirection::South => (size - 1 - b, a),
-                TiltDirection::East => (a, size - 1 - b),
-            };
-            if grid[r][c] != Tile::Round {
-                continue;
-            }
+const SPIN: [TiltDirection; 4] = [
+    TiltDirection::North,
+    TiltDirection::West,
+    TiltDirection::South,
+    TiltDirection::East,
+];
+
+fn parse_order(s: &str) -> Result<Vec<TiltDirection>, String> {
+    s.chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'N' => Ok(TiltDirection::North),
+            'S' => Ok(TiltDirection::South),
+            'E' => Ok(TiltDirection::East),
+            'W' => Ok(TiltDirection::West),
+            _ => Err(format!("Unknown direction {}", c)),
+        })
+        .collect()
+}
 
-            let (mut s_r, mut s_c) = (r as isize, c as isize);
-            loop {
-                let (n_r, n_c) = match dir {
-                    TiltDirection::North => (s_r - 1, s_c),
-                    TiltDirection::West => (s_r, s_c - 1),
-                    TiltDirection::South => (s_r + 1, s_c),
-                    TiltDirection::East => (s_r, s_c + 1),
-                };
-                if n_r < 0 || n_r >= size as isize || n_c < 0 || n_c >= size as isize {
-                    break;
+// rolls every round rock as far as it goes, in place. each column (or row) is swept once
+// from the wall it tilts towards, keeping track of where the next rock will come to rest
+fn tilt(grid: &mut [Vec<Tile>], dir: TiltDirection) {
+    let num_rows = grid.len();
+    let num_cols = grid[0].len();
+
+    let (num_lines, line_len) = match dir {
+        TiltDirection::North | TiltDirection::South => (num_cols, num_rows),
+        TiltDirection::East | TiltDirection::West => (num_rows, num_cols),
+    };
+    // position b along line a, counting from the wall
+    let cell = |a: usize, b: usize| match dir {
+        TiltDirection::North => (b, a),
+        TiltDirection::West => (a, b),
+        TiltDirection::South => (num_rows - 1 - b, a),
+        TiltDirection::East => (a, num_cols - 1 - b),
+    };
+
+    for a in 0..num_lines {
+        let mut free = 0;
+        for b in 0..line_len {
+            let (r, c) = cell(a, b);
+            match grid[r][c] {
+                Tile::Cube => free = b + 1,
+                Tile::Round => {
+                    if free != b {
+                        let (f_r, f_c) = cell(a, free);
+                        grid[f_r][f_c] = Tile::Round;
+                        grid[r][c] = Tile::Empty;
+                    }
+                    free += 1;
                 }
-                if grid[n_r as usize][n_c as usize] != Tile::Empty {
-                    break;
-                }
-
-                (s_r, s_c) = (n_r, n_c);
+                Tile::Empty => (),
             }
-
-            grid[r][c] = Tile::Empty;
-            grid[s_r as usize][s_c as usize] = Tile::Round;
         }
     }
-
-    grid
 }
 
-fn get_key(grid: &Vec<Vec<Tile>>) -> String {
-    grid.iter()
-        .flat_map(|row| {
-            row.iter().map(|r| match r {
-                Tile::Round => 'O',
-                Tile::Empty => '.',
-                Tile::Cube => '#',
-            })
-        })
-        .collect::<String>()
-}
-
-fn spin_cycle(grid: &Vec<Vec<Tile>>, cycle_count: usize) -> usize {
-    let mut grid = grid.clone();
-    let s = get_key(&grid);
-
-    let mut seen = HashMap::new();
-    let mut history = vec![];
-    history.push(get_pressure(&grid));
-    seen.insert(s, 0);
-
-    for i in 1..=cycle_count {
-        grid = tilt(&grid, TiltDirection::North);
-        grid = tilt(&grid, TiltDirection::West);
-        grid = tilt(&grid, TiltDirection::South);
-        grid = tilt(&grid, TiltDirection::East);
-
-        let s = get_key(&grid);
-
-        if let Some(&prev_seen) = seen.get(&s) {
-            let period = i - prev_seen;
-            return history[prev_seen + (cycle_count - prev_seen) % period];
-        }
-
-        seen.insert(s, i);
-        history.push(get_pressure(&grid));
+// one spin: a tilt in each direction of the sequence, in turn
+fn spin(grid: &[Vec<Tile>], order: &[TiltDirection]) -> Vec<Vec<Tile>> {
+    let mut grid = grid.to_vec();
+    for &dir in order {
+        tilt(&mut grid, dir);
     }
+    grid
+}
 
-    panic!("Not found");
+// the load after cycle_count spins, or None if no cycle turns up within max_steps spins
+// and so the state can't be worked out
+fn spin_cycle(
+    grid: &[Vec<Tile>],
+    order: &[TiltDirection],
+    cycle_count: usize,
+    max_steps: usize,
+) -> (Option<usize>, History<Vec<Vec<Tile>>>) {
+    let history = History::explore(
+        grid.to_vec(),
+        |grid| spin(grid, order),
+        cycle_count.min(max_steps),
+    );
+    let load = history.state_at(cycle_count).map(|grid| get_pressure(grid));
+    (load, history)
 }
 
-fn get_pressure(grid: &Vec<Vec<Tile>>) -> usize {
+fn get_pressure(grid: &[Vec<Tile>]) -> usize {
     grid.iter()
         .rev()
         .enumerate()
@@ -137,8 +144,99 @@ fn main() {
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
     let platform = parse_input(&raw_input);
 
-    let part_one = get_pressure(&tilt(&platform, TiltDirection::North));
-    let part_two = spin_cycle(&platform, 1000000000);
+    // --order sets the spin as a sequence of directions such as NWSE, --cycles the
+    // number of spins and --max-steps how long to look for a cycle.
+    // --animate plays every tilt in the terminal, --delay MS apart, and --gif PATH and
+    // --svg DIR export the frames, --scale pixels to a cell for the gif. without
+    // --cycles the animation runs until the cycle has gone round once
+    let mut order = SPIN.to_vec();
+    let mut cycles = None;
+    let mut max_steps = usize::MAX;
+    let mut animate = false;
+    let mut delay = 100;
+    let mut gif = None;
+    let mut svg = None;
+    let mut scale = 4;
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "--animate" {
+            animate = true;
+            continue;
+        }
+        let val = args
+            .next()
+            .unwrap_or_else(|| panic!("{} needs a value", flag));
+        let num = || {
+            val.parse::<usize>()
+                .unwrap_or_else(|_| panic!("{} needs a number", flag))
+        };
+        match flag.as_str() {
+            "--order" => order = parse_order(&val).unwrap_or_else(|err| panic!("{}", err)),
+            "--cycles" => cycles = Some(num()),
+            "--max-steps" => max_steps = num(),
+            "--delay" => delay = num(),
+            "--gif" => gif = Some(val.clone()),
+            "--svg" => svg = Some(val.clone()),
+            "--scale" => scale = num().max(1),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+
+    if animate || gif.is_some() || svg.is_some() {
+        let (_, history) = spin_cycle(&platform, &order, usize::MAX, max_steps);
+        let cycle = history.prefix().zip(history.period());
+        let spins = cycles.unwrap_or(match cycle {
+            Some((prefix, period)) => prefix + period,
+            None => 1,
+        });
+        let frames = visual::frames(&platform, &order, spins, cycle);
+
+        if animate {
+            for frame in frames.iter() {
+                print!("{}", visual::render(frame));
+                thread::sleep(Duration::from_millis(delay as u64));
+            }
+        }
+        if let Some(path) = gif {
+            let delay = (delay / 10).min(u16::MAX as usize) as u16;
+            visual::write_gif(&frames, Path::new(&path), scale, delay)
+                .unwrap_or_else(|err| panic!("Could not write {}: {}", path, err));
+        }
+        if let Some(dir) = svg {
+            visual::write_svgs(&frames, Path::new(&dir))
+                .unwrap_or_else(|err| panic!("Could not write {}: {}", dir, err));
+        }
+        match cycle {
+            Some((prefix, period)) => println!("Cycle: prefix {}, period {}", prefix, period),
+            None => println!("Cycle: none found"),
+        }
+        println!("Frames: {}", frames.len());
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
+
+    if cycles.is_some() || order != SPIN {
+        let cycles = cycles.unwrap_or(1000000000);
+        let (load, history) = spin_cycle(&platform, &order, cycles, max_steps);
+        match (history.prefix(), history.period()) {
+            (Some(prefix), Some(period)) => {
+                println!("Cycle: prefix {}, period {}", prefix, period)
+            }
+            _ => println!("Cycle: none found"),
+        }
+        match load {
+            Some(load) => println!("Load after {} spins: {}", cycles, load),
+            None => println!("Load after {} spins: unknown", cycles),
+        }
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
+
+    let mut tilted = platform.clone();
+    tilt(&mut tilted, TiltDirection::North);
+    let part_one = get_pressure(&tilted);
+    let (part_two, _) = spin_cycle(&platform, &SPIN, 1000000000, usize::MAX);
+    let part_two = part_two.expect("No cycle found");
 
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
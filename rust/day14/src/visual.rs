@@ -0,0 +1,299 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{get_pressure, tilt, Tile, TiltDirection};
+
+// one tilt of the platform. a frame is a boundary when it ends the spin at which the
+// cycle starts, or a whole number of periods after it
+pub struct Frame {
+    pub grid: Vec<Vec<Tile>>,
+    pub spin: usize,
+    pub dir: Option<TiltDirection>,
+    pub load: usize,
+    pub boundary: bool,
+}
+
+impl Frame {
+    pub fn label(&self) -> String {
+        match self.dir {
+            Some(dir) => format!("spin {}, tilt {:?}, load {}", self.spin, dir, self.load),
+            None => format!("start, load {}", self.load),
+        }
+    }
+}
+
+// every tilt of spins spins, starting from the platform as it is
+pub fn frames(
+    platform: &[Vec<Tile>],
+    order: &[TiltDirection],
+    spins: usize,
+    cycle: Option<(usize, usize)>,
+) -> Vec<Frame> {
+    let is_boundary = |spin: usize| match cycle {
+        Some((prefix, period)) => spin >= prefix && (spin - prefix).is_multiple_of(period),
+        None => false,
+    };
+
+    let mut grid = platform.to_vec();
+    let mut frames = vec![Frame {
+        grid: grid.clone(),
+        spin: 0,
+        dir: None,
+        load: get_pressure(&grid),
+        boundary: is_boundary(0),
+    }];
+    for spin in 1..=spins {
+        for (i, &dir) in order.iter().enumerate() {
+            tilt(&mut grid, dir);
+            frames.push(Frame {
+                grid: grid.clone(),
+                spin,
+                dir: Some(dir),
+                load: get_pressure(&grid),
+                boundary: i == order.len() - 1 && is_boundary(spin),
+            });
+        }
+    }
+
+    frames
+}
+
+// the frame for a terminal, drawn from the top left of the screen
+pub fn render(frame: &Frame) -> String {
+    let mut out = String::from("\x1b[H\x1b[2J");
+    for row in frame.grid.iter() {
+        for tile in row {
+            out.push_str(match tile {
+                Tile::Round => "\x1b[1;33mO",
+                Tile::Cube => "\x1b[0;2m#",
+                Tile::Empty => "\x1b[0m.",
+            });
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out.push_str(&frame.label());
+    if frame.boundary {
+        out.push_str(" \x1b[1;31m<< cycle boundary\x1b[0m");
+    }
+    out.push('\n');
+    out
+}
+
+const CELL: usize = 10;
+
+// one svg per frame, named frame_0000.svg and so on. boundaries get a red border
+pub fn write_svgs(frames: &[Frame], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let height = frame.grid.len() * CELL;
+        let width = frame.grid[0].len() * CELL;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            width,
+            height + 2 * CELL
+        );
+        svg.push_str(&format!(
+            "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+            width, height
+        ));
+        for (r, row) in frame.grid.iter().enumerate() {
+            for (c, tile) in row.iter().enumerate() {
+                let (x, y) = (c * CELL, r * CELL);
+                match tile {
+                    Tile::Round => svg.push_str(&format!(
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"goldenrod\"/>\n",
+                        x + CELL / 2,
+                        y + CELL / 2,
+                        CELL * 2 / 5
+                    )),
+                    Tile::Cube => svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"dimgray\"/>\n",
+                        x, y, CELL, CELL
+                    )),
+                    Tile::Empty => (),
+                }
+            }
+        }
+        if frame.boundary {
+            svg.push_str(&format!(
+                "<rect width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"crimson\" stroke-width=\"4\"/>\n",
+                width, height
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"2\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\">{}{}</text>\n",
+            height + CELL * 3 / 2,
+            CELL,
+            frame.label(),
+            if frame.boundary {
+                " (cycle boundary)"
+            } else {
+                ""
+            }
+        ));
+        svg.push_str("</svg>\n");
+
+        fs::write(dir.join(format!("frame_{:04}.svg", i)), svg)?;
+    }
+
+    Ok(())
+}
+
+// background, cube, round rock and the boundary border
+const PALETTE: [[u8; 3]; 4] = [[250, 250, 250], [96, 96, 96], [218, 165, 32], [220, 20, 60]];
+
+// an animated gif with scale pixels to a cell and delay hundredths of a second between
+// frames. the border around the platform turns red on cycle boundaries
+pub fn write_gif(frames: &[Frame], path: &Path, scale: usize, delay: u16) -> io::Result<()> {
+    let num_rows = frames[0].grid.len();
+    let num_cols = frames[0].grid[0].len();
+    // gif dimensions are 16 bit
+    let size = |cells: usize| {
+        (cells + 2)
+            .checked_mul(scale)
+            .and_then(|px| u16::try_from(px).ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} cells at scale {} is more than {} pixels",
+                        cells,
+                        scale,
+                        u16::MAX
+                    ),
+                )
+            })
+    };
+    let width = size(num_cols)?;
+    let height = size(num_rows)?;
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend(width.to_le_bytes());
+    gif.extend(height.to_le_bytes());
+    // global colour table of 4 entries, 8 bits per primary
+    gif.extend([0b1111_0001, 0, 0]);
+    gif.extend(PALETTE.iter().flatten());
+    // loop forever
+    gif.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for frame in frames {
+        let border = if frame.boundary { 3 } else { 0 };
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let (r, c) = (y / scale, x / scale);
+                if r == 0 || c == 0 || r > num_rows || c > num_cols {
+                    pixels.push(border);
+                } else {
+                    pixels.push(match frame.grid[r - 1][c - 1] {
+                        Tile::Empty => 0,
+                        Tile::Cube => 1,
+                        Tile::Round => 2,
+                    });
+                }
+            }
+        }
+
+        // graphic control extension for the delay, then the image itself
+        gif.extend([0x21, 0xf9, 0x04, 0x00]);
+        gif.extend(delay.to_le_bytes());
+        gif.extend([0x00, 0x00]);
+        gif.push(0x2c);
+        gif.extend([0, 0, 0, 0]);
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        gif.push(0);
+        gif.push(MIN_CODE_SIZE);
+        for block in lzw(&pixels).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+
+    fs::write(path, gif)
+}
+
+const MIN_CODE_SIZE: u8 = 2;
+
+// packs codes of varying width, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+// gif flavoured lzw: codes start one bit wider than the colour indices and grow as the
+// table fills, keeping step with a decoder that adds an entry for every code it reads
+// after the first. the table is cleared when it reaches 4096 entries
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::default();
+    // the table maps (prefix code, next index) to a code, laid out flat
+    let mut table = vec![u16::MAX; 4096 * PALETTE.len()];
+    let mut next = end + 1;
+    let mut width = MIN_CODE_SIZE as u32 + 1;
+    writer.write(clear, width);
+
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &p in rest {
+        let slot = prefix as usize * PALETTE.len() + p as usize;
+        if table[slot] != u16::MAX {
+            prefix = table[slot];
+            continue;
+        }
+
+        writer.write(prefix, width);
+        if next < 4096 {
+            table[slot] = next;
+            next += 1;
+            if next > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            writer.write(clear, width);
+            table.fill(u16::MAX);
+            next = end + 1;
+            width = MIN_CODE_SIZE as u32 + 1;
+        }
+        prefix = p as u16;
+    }
+    writer.write(prefix, width);
+    // the decoder still counts an entry for the last code, which can widen the end code
+    if next < 4096 && next + 1 > 1 << width && width < 12 {
+        width += 1;
+    }
+    writer.write(end, width);
+
+    writer.finish()
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::hash;
+
+// the lenses in one box in the order they went in. removed lenses leave an empty slot
+// behind so that the slots of the others stay put, and the slots are compacted once
+// more than half of them are empty
+#[derive(Clone, Default)]
+struct LensBox {
+    slots: Vec<Option<(String, usize)>>,
+    index: HashMap<String, usize>,
+}
+
+impl LensBox {
+    fn insert(&mut self, label: &str, focal: usize) -> Option<usize> {
+        if let Some(&slot) = self.index.get(label) {
+            let lens = self.slots[slot].as_mut().unwrap();
+            Some(std::mem::replace(&mut lens.1, focal))
+        } else {
+            self.index.insert(String::from(label), self.slots.len());
+            self.slots.push(Some((String::from(label), focal)));
+            None
+        }
+    }
+
+    fn remove(&mut self, label: &str) -> Option<usize> {
+        let slot = self.index.remove(label)?;
+        let (_, focal) = self.slots[slot].take().unwrap();
+        if self.slots.len() > 2 * self.index.len() {
+            self.compact();
+        }
+        Some(focal)
+    }
+
+    fn compact(&mut self) {
+        self.slots.retain(|slot| slot.is_some());
+        for (i, slot) in self.slots.iter().enumerate() {
+            let (label, _) = slot.as_ref().unwrap();
+            *self.index.get_mut(label).unwrap() = i;
+        }
+    }
+
+    fn get(&self, label: &str) -> Option<usize> {
+        let slot = *self.index.get(label)?;
+        self.slots[slot].as_ref().map(|(_, focal)| *focal)
+    }
+
+    fn lenses(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|(label, focal)| (label.as_str(), *focal))
+    }
+}
+
+// the boxes of the HASHMAP procedure. which box a label goes in is decided by the hash
+// function, the puzzle's own unless another is given
+#[derive(Clone)]
+pub struct LensLibrary {
+    boxes: Vec<LensBox>,
+    hasher: fn(&str) -> usize,
+}
+
+impl LensLibrary {
+    pub fn new() -> Self {
+        Self::with_hasher(hash, 256)
+    }
+
+    pub fn with_hasher(hasher: fn(&str) -> usize, num_boxes: usize) -> Self {
+        Self {
+            boxes: vec![LensBox::default(); num_boxes],
+            hasher,
+        }
+    }
+
+    pub fn box_of(&self, label: &str) -> usize {
+        (self.hasher)(label) % self.boxes.len()
+    }
+
+    // puts a lens in its box, replacing any lens with the same label in place. returns
+    // the focal length of the lens it replaced
+    pub fn insert(&mut self, label: &str, focal: usize) -> Option<usize> {
+        let box_num = self.box_of(label);
+        self.boxes[box_num].insert(label, focal)
+    }
+
+    // takes a lens out of its box, returning its focal length if it was there
+    pub fn remove(&mut self, label: &str) -> Option<usize> {
+        let box_num = self.box_of(label);
+        self.boxes[box_num].remove(label)
+    }
+
+    pub fn get(&self, label: &str) -> Option<usize> {
+        self.boxes[self.box_of(label)].get(label)
+    }
+
+    // the lenses in one box, front to back
+    pub fn lenses(&self, box_num: usize) -> impl Iterator<Item = (&str, usize)> {
+        self.boxes[box_num].lenses()
+    }
+
+    // every lens as (box, label, focal length), box by box and front to back
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str, usize)> {
+        self.boxes.iter().enumerate().flat_map(|(i, lens_box)| {
+            lens_box
+                .lenses()
+                .map(move |(label, focal)| (i, label, focal))
+        })
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        (0..self.boxes.len())
+            .map(|i| {
+                self.lenses(i)
+                    .enumerate()
+                    .map(|(j, (_, focal))| (i + 1) * (j + 1) * focal)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    // the contents of one box, written as the puzzle does: Box 3: [ot 7] [ab 5]
+    pub fn snapshot(&self, box_num: usize) -> String {
+        let mut line = format!("Box {}:", box_num);
+        for (label, focal) in self.lenses(box_num) {
+            line.push_str(&format!(" [{} {}]", label, focal));
+        }
+        line
+    }
+}
+
+impl Default for LensLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// every box that has a lens in it, one to a line
+impl fmt::Display for LensLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.boxes.len() {
+            if self.lenses(i).next().is_some() {
+                writeln!(f, "{}", self.snapshot(i))?;
+            }
+        }
+        Ok(())
+    }
+}
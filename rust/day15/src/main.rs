@@ -1,6 +1,15 @@
-use std::fs;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
 use std::time::Instant;
 
+mod library;
+mod trace;
+
+use library::LensLibrary;
+use trace::{Filter, Step};
+
 const FILEPATH: &str = "./input/input.txt";
 
 fn hash(s: &str) -> usize {
@@ -8,51 +17,127 @@ fn hash(s: &str) -> usize {
         .fold(0, |val, ch| (val + (ch as u8 as usize)) * 17 % 256)
 }
 
-fn solve(input: &Vec<String>) -> (usize, usize) {
-    let mut chk = 0;
-    let mut boxes: Vec<Vec<(String, usize)>> = vec![vec![]; 256];
-
-    for line in input.iter() {
-        chk += hash(line);
-        if line.contains('=') {
-            let mut pcs = line.split("=");
-            let label = String::from(pcs.next().unwrap());
-            let focal_length = pcs.next().unwrap().parse::<usize>().unwrap();
-            let box_num = hash(&label);
-            let box_ = boxes.get_mut(box_num).unwrap();
-
-            if let Some((i, _)) = box_.iter().enumerate().find(|(_, val)| val.0 == label) {
-                box_[i] = (label, focal_length);
-            } else {
-                box_.push((label, focal_length));
-            }
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Operation {
+    Set { label: String, focal: usize },
+    Remove { label: String },
+}
+
+impl Operation {
+    fn label(&self) -> &str {
+        match self {
+            Operation::Set { label, .. } | Operation::Remove { label } => label,
+        }
+    }
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (label, focal) = if s.is_empty() {
+            return Err(String::from("empty step"));
+        } else if let Some(label) = s.strip_suffix('-') {
+            (label, None)
+        } else if let Some((label, focal)) = s.split_once('=') {
+            (label, Some(focal))
         } else {
-            let label = line.split("-").next().unwrap();
-            let box_num = hash(&label);
-            let box_ = boxes.get_mut(box_num).unwrap();
-            if let Some((i, _)) = box_.iter().enumerate().find(|(_, val)| val.0 == label) {
-                box_.remove(i);
-            }
+            return Err(format!("{:?} is neither a set (=) nor a removal (-)", s));
+        };
+
+        if label.is_empty() {
+            return Err(format!("{:?} has no label", s));
         }
+        if let Some(c) = label.chars().find(|c| !c.is_ascii_lowercase()) {
+            return Err(format!("label {:?} has illegal character {:?}", label, c));
+        }
+
+        let label = String::from(label);
+        match focal {
+            None => Ok(Operation::Remove { label }),
+            // a single digit, so that "01" or "+1" can't pass for "1" and be written back
+            // out (and hashed) differently
+            Some(focal) => match focal.as_bytes() {
+                &[d @ b'1'..=b'9'] => Ok(Operation::Set {
+                    label,
+                    focal: (d - b'0') as usize,
+                }),
+                _ => Err(format!(
+                    "focal length {:?} is not a digit from 1 to 9",
+                    focal
+                )),
+            },
+        }
+    }
+}
+
+// written back out exactly as the step appears in the sequence (less any whitespace),
+// which is also what gets hashed
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::Set { label, focal } => write!(f, "{}={}", label, focal),
+            Operation::Remove { label } => write!(f, "{}-", label),
+        }
+    }
+}
+
+// runs the sequence, handing each step and the library as it stands after the step to
+// on_step
+fn solve<F: FnMut(&Step, &LensLibrary)>(
+    input: &[Operation],
+    mut on_step: F,
+) -> (usize, LensLibrary) {
+    let mut chk = 0;
+    let mut library = LensLibrary::new();
+
+    for (index, op) in input.iter().enumerate() {
+        let text = op.to_string();
+        let step_hash = hash(&text);
+        chk += step_hash;
+        let changed = match op {
+            Operation::Set { label, focal } => library.insert(label, *focal) != Some(*focal),
+            Operation::Remove { label } => library.remove(label).is_some(),
+        };
+
+        let step = Step {
+            index,
+            text: &text,
+            hash: step_hash,
+            label: op.label(),
+            box_num: library.box_of(op.label()),
+            changed,
+        };
+        on_step(&step, &library);
     }
 
-    let power = boxes
-        .into_iter()
-        .enumerate()
-        .map(|(i, lenses)| {
-            lenses
-                .iter()
-                .enumerate()
-                .map(|(j, (_, power))| (i + 1) * (j + 1) * power)
-                .sum::<usize>()
-        })
-        .sum();
-
-    (chk, power)
+    (chk, library)
 }
 
-fn parse_input(input: &str) -> Vec<String> {
-    input.trim().split(",").map(|s| String::from(s)).collect()
+enum Query {
+    Boxes,
+    Lenses,
+    Lens(String),
+}
+
+// whitespace, newlines included, is ignored anywhere in the sequence. every bad step is
+// reported, by its index
+fn parse_input(input: &str) -> Result<Vec<Operation>, String> {
+    let sequence = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    let mut ops = vec![];
+    let mut errors = vec![];
+    for (i, step) in sequence.split(',').enumerate() {
+        match Operation::from_str(step) {
+            Ok(op) => ops.push(op),
+            Err(err) => errors.push(format!("Step {}: {}", i, err)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(ops)
+    } else {
+        Err(errors.join("\n"))
+    }
 }
 
 fn main() {
@@ -62,9 +147,79 @@ fn main() {
     let now = Instant::now();
 
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
-    let insts = parse_input(&raw_input);
+    let insts = match parse_input(&raw_input) {
+        Ok(insts) => insts,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
 
-    let (part_one, part_two) = solve(&insts);
+    // --trace prints every step with the box it went to, narrowed down to particular
+    // labels or boxes by --label L and --box N, and --json PATH writes every step as json
+    // lines. once the sequence has run, --boxes prints the contents of every box,
+    // --lenses every lens in order and --lens LABEL the lens with that label
+    let mut trace = false;
+    let mut filter = Filter::default();
+    let mut json = None;
+    let mut queries = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut val = || {
+            args.next()
+                .unwrap_or_else(|| panic!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "--trace" => trace = true,
+            "--label" => filter.labels.push(val()),
+            "--box" => filter.boxes.push(
+                val()
+                    .parse::<usize>()
+                    .unwrap_or_else(|_| panic!("--box needs a number")),
+            ),
+            "--json" => {
+                let path = val();
+                let file = File::create(&path)
+                    .unwrap_or_else(|err| panic!("Could not create {}: {}", path, err));
+                json = Some(BufWriter::new(file));
+            }
+            "--boxes" => queries.push(Query::Boxes),
+            "--lenses" => queries.push(Query::Lenses),
+            "--lens" => queries.push(Query::Lens(val())),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+    let trace = trace || !filter.labels.is_empty() || !filter.boxes.is_empty();
+
+    let (part_one, library) = solve(&insts, |step, library| {
+        if trace && filter.matches(step) {
+            println!("{}", trace::describe(step, library));
+        }
+        if let Some(out) = json.as_mut() {
+            writeln!(out, "{}", trace::to_json(step, library)).expect("Could not write trace");
+        }
+    });
+    let part_two = library.focusing_power();
+
+    for query in queries {
+        match query {
+            Query::Boxes => print!("{}", library),
+            Query::Lenses => {
+                for (box_num, label, focal) in library.iter() {
+                    println!("{} {} {}", box_num, label, focal);
+                }
+            }
+            Query::Lens(label) => match library.get(&label) {
+                Some(focal) => println!(
+                    "{}: focal length {} in box {}",
+                    label,
+                    focal,
+                    library.box_of(&label)
+                ),
+                None => println!("{}: not in any box", label),
+            },
+        }
+    }
 
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);
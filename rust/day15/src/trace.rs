@@ -0,0 +1,74 @@
+use crate::library::LensLibrary;
+
+// what one step of the sequence did
+pub struct Step<'a> {
+    pub index: usize,
+    pub text: &'a str,
+    pub hash: usize,
+    pub label: &'a str,
+    pub box_num: usize,
+    pub changed: bool,
+}
+
+// which steps to show: those touching any of the labels or boxes, or every step if
+// neither is given
+#[derive(Default)]
+pub struct Filter {
+    pub labels: Vec<String>,
+    pub boxes: Vec<usize>,
+}
+
+impl Filter {
+    pub fn matches(&self, step: &Step) -> bool {
+        (self.labels.is_empty() && self.boxes.is_empty())
+            || self.labels.iter().any(|label| label == step.label)
+            || self.boxes.contains(&step.box_num)
+    }
+}
+
+// e.g. 3: qp=3 (hash 97) changed Box 1: [qp 3]
+pub fn describe(step: &Step, library: &LensLibrary) -> String {
+    format!(
+        "{}: {} (hash {}) {} {}",
+        step.index,
+        step.text,
+        step.hash,
+        if step.changed { "changed" } else { "unchanged" },
+        library.snapshot(step.box_num)
+    )
+}
+
+// the step as a line of json, with the box's contents after it
+pub fn to_json(step: &Step, library: &LensLibrary) -> String {
+    let lenses = library
+        .lenses(step.box_num)
+        .map(|(label, focal)| format!("[{},{}]", json_string(label), focal))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"index\":{},\"step\":{},\"hash\":{},\"label\":{},\"box\":{},\"changed\":{},\"lenses\":[{}]}}",
+        step.index,
+        json_string(step.text),
+        step.hash,
+        json_string(step.label),
+        step.box_num,
+        step.changed,
+        lenses.join(",")
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
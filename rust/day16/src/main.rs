@@ -1,105 +1,173 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
 const FILEPATH: &str = "./input/input.txt";
 
-fn trace(
-    size: i32,
-    grid: &HashMap<(i32, i32), char>,
-    start_pos: (i32, i32),
-    start_loc: (i32, i32),
-) -> usize {
-    let mut visited = HashMap::<(i32, i32), HashSet<(i32, i32)>>::new();
-    visited.insert((0, 1), HashSet::new());
-    visited.insert((0, -1), HashSet::new());
-    visited.insert((1, 0), HashSet::new());
-    visited.insert((-1, 0), HashSet::new());
-
-    let mut starts = vec![(start_pos, start_loc)];
-
-    while let Some(start) = starts.pop() {
-        let (mut pos, mut dir) = start;
-
-        loop {
-            let next = (pos.0 + dir.0, pos.1 + dir.1);
-            if next.0 < 0 || next.1 < 0 || next.0 >= size || next.1 >= size {
-                break;
-            }
+// the four directions a beam can travel, and how each is drawn. a tile's beams are kept
+// as a bitmask over these
+const DIRS: [((i32, i32), char); 4] =
+    [((0, 1), '>'), ((1, 0), 'v'), ((0, -1), '<'), ((-1, 0), '^')];
 
-            if visited[&dir].contains(&next) {
-                break;
-            }
+// a position just outside the grid and the direction a beam enters from it
+type Entry = ((i32, i32), (i32, i32));
+
+fn dir_bit(dir: (i32, i32)) -> u8 {
+    1 << DIRS.iter().position(|&(d, _)| d == dir).unwrap()
+}
+
+struct Contraption {
+    grid: Vec<Vec<char>>,
+    num_rows: i32,
+    num_cols: i32,
+}
+
+impl Contraption {
+    fn contains(&self, (r, c): (i32, i32)) -> bool {
+        r >= 0 && c >= 0 && r < self.num_rows && c < self.num_cols
+    }
+
+    // the directions that beams cross each tile in, for a beam that enters the grid
+    // from start_pos (just outside it) heading start_dir
+    fn trace(&self, start_pos: (i32, i32), start_dir: (i32, i32)) -> Vec<Vec<u8>> {
+        let mut visited = vec![vec![0u8; self.num_cols as usize]; self.num_rows as usize];
 
-            visited.get_mut(&dir).unwrap().insert(next.clone());
+        let mut starts = vec![(start_pos, start_dir)];
 
-            pos = next;
+        while let Some(start) = starts.pop() {
+            let (mut pos, mut dir) = start;
 
-            let (d_r, d_c) = dir;
+            loop {
+                let next = (pos.0 + dir.0, pos.1 + dir.1);
+                if !self.contains(next) {
+                    break;
+                }
 
-            dir = match grid[&next] {
-                '-' if d_c == 0 => {
-                    starts.push((pos, (0, 1)));
-                    (0, -1)
+                let seen = &mut visited[next.0 as usize][next.1 as usize];
+                if *seen & dir_bit(dir) != 0 {
+                    break;
                 }
-                '|' if d_r == 0 => {
-                    starts.push((pos, (1, 0)));
-                    (-1, 0)
+                *seen |= dir_bit(dir);
+
+                pos = next;
+
+                let (d_r, d_c) = dir;
+
+                dir = match self.grid[next.0 as usize][next.1 as usize] {
+                    '-' if d_c == 0 => {
+                        starts.push((pos, (0, 1)));
+                        (0, -1)
+                    }
+                    '|' if d_r == 0 => {
+                        starts.push((pos, (1, 0)));
+                        (-1, 0)
+                    }
+                    '/' => (-d_c, -d_r),
+                    '\\' => (d_c, d_r),
+                    _ => dir,
                 }
-                '/' => (-d_c, -d_r),
-                '\\' => (d_c, d_r),
-                _ => dir,
             }
         }
+
+        visited
     }
 
-    visited
-        .values()
-        .flat_map(|v| v)
-        .collect::<HashSet<_>>()
-        .len()
+    fn energised(&self, start_pos: (i32, i32), start_dir: (i32, i32)) -> HashSet<(i32, i32)> {
+        let visited = self.trace(start_pos, start_dir);
+        (0..self.num_rows)
+            .flat_map(|r| (0..self.num_cols).map(move |c| (r, c)))
+            .filter(|&(r, c)| visited[r as usize][c as usize] != 0)
+            .collect()
+    }
+
+    // every way into the grid: along the left and right edges, then the top and bottom
+    fn entry_points(&self) -> Vec<Entry> {
+        let rows =
+            (0..self.num_rows).flat_map(|r| [((r, -1), (0, 1)), ((r, self.num_cols), (0, -1))]);
+        let cols =
+            (0..self.num_cols).flat_map(|c| [((-1, c), (1, 0)), ((self.num_rows, c), (-1, 0))]);
+        rows.chain(cols).collect()
+    }
+
+    // the energised tiles for every entry point
+    fn energised_by_entry(&self) -> Vec<(Entry, HashSet<(i32, i32)>)> {
+        self.entry_points()
+            .into_iter()
+            .map(|(pos, dir)| ((pos, dir), self.energised(pos, dir)))
+            .collect()
+    }
+
+    // energised tiles as '#' and the rest as '.'
+    fn render_energised(&self, energised: &HashSet<(i32, i32)>) -> String {
+        (0..self.num_rows)
+            .map(|r| {
+                let mut line = (0..self.num_cols)
+                    .map(|c| {
+                        if energised.contains(&(r, c)) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
+
+    // the beams over the empty tiles: an arrow where one beam passes, or how many cross
+    // there. mirrors and splitters are drawn as they are
+    fn render_beams(&self, visited: &[Vec<u8>]) -> String {
+        (0..self.num_rows as usize)
+            .map(|r| {
+                let mut line = (0..self.num_cols as usize)
+                    .map(|c| match (self.grid[r][c], visited[r][c].count_ones()) {
+                        ('.', 0) => '.',
+                        ('.', 1) => DIRS[visited[r][c].trailing_zeros() as usize].1,
+                        ('.', n) => char::from_digit(n, 10).unwrap(),
+                        (ch, _) => ch,
+                    })
+                    .collect::<String>();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
 }
 
-fn solve_part_one(size: i32, grid: &HashMap<(i32, i32), char>) -> usize {
-    trace(size, grid, (0, -1), (0, 1))
+fn solve_part_one(contraption: &Contraption) -> usize {
+    contraption.energised((0, -1), (0, 1)).len()
 }
 
-fn solve_part_two(size: i32, grid: &HashMap<(i32, i32), char>) -> usize {
-    (0..size)
-        .map(|s| {
-            let vals = vec![
-                trace(size, &grid, (s, -1), (0, 1)),
-                trace(size, &grid, (s, size), (0, -1)),
-                trace(size, &grid, (-1, s), (1, 0)),
-                trace(size, &grid, (size, s), (-1, 0)),
-            ];
-            vals.into_iter().max().unwrap()
-        })
+fn solve_part_two(contraption: &Contraption) -> usize {
+    contraption
+        .energised_by_entry()
+        .iter()
+        .map(|(_, energised)| energised.len())
         .max()
         .unwrap()
 }
 
-fn parse_input(input: &str) -> (i32, HashMap<(i32, i32), char>) {
+fn parse_input(input: &str) -> Contraption {
     let grid = input
         .trim()
         .lines()
-        .enumerate()
-        .flat_map(|(r, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(c, ch)| ((r as i32, c as i32), ch))
-                .collect::<Vec<_>>()
-        })
-        .collect::<HashMap<(i32, i32), char>>();
-
-    let size = {
-        let &max_r = grid.keys().map(|(r, _)| r).max().unwrap();
-        let &max_c = grid.keys().map(|(r, _)| r).max().unwrap();
-        assert_eq!(max_c, max_r);
-        max_r + 1
-    };
-
-    (size as i32, grid)
+        .map(|line| line.trim().chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let num_cols = grid[0].len();
+    assert!(
+        grid.iter().all(|row| row.len() == num_cols),
+        "Rows must all be the same length"
+    );
+
+    Contraption {
+        num_rows: grid.len() as i32,
+        num_cols: num_cols as i32,
+        grid,
+    }
 }
 
 fn main() {
@@ -110,10 +178,62 @@ fn main() {
 
     let raw_input = fs::read_to_string(FILEPATH).expect("Could not read file");
 
-    let (size, grid) = parse_input(&raw_input);
+    let contraption = parse_input(&raw_input);
+
+    // --render draws the energised tiles and the beams for a beam entering from the
+    // top left heading right, or from --entry R C D (D one of > v < ^, with R C the
+    // position just outside the grid), or for the best entry with --best
+    let mut render = false;
+    let mut entry = ((0, -1), (0, 1));
+    let mut best = false;
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--render" => render = true,
+            "--best" => best = true,
+            "--entry" => {
+                let mut val = || args.next().expect("--entry needs R C D");
+                let num = |s: String| s.parse::<i32>().expect("--entry needs R C D");
+                let (r, c) = (num(val()), num(val()));
+                let d = val();
+                let dir = DIRS
+                    .iter()
+                    .find(|&&(_, ch)| d == ch.to_string())
+                    .unwrap_or_else(|| panic!("Unknown direction {}", d))
+                    .0;
+                entry = ((r, c), dir);
+            }
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+
+    if render || best {
+        if best {
+            entry = contraption
+                .energised_by_entry()
+                .into_iter()
+                .max_by_key(|(_, energised)| energised.len())
+                .unwrap()
+                .0;
+        }
+        let (pos, dir) = entry;
+        let energised = contraption.energised(pos, dir);
+        print!("{}", contraption.render_energised(&energised));
+        println!();
+        print!("{}", contraption.render_beams(&contraption.trace(pos, dir)));
+        println!(
+            "Entry ({},{}) heading {}: {} energised",
+            pos.0,
+            pos.1,
+            DIRS[dir_bit(dir).trailing_zeros() as usize].1,
+            energised.len()
+        );
+        println!("Elasped time: {}ms", now.elapsed().as_millis());
+        return;
+    }
 
-    let part_one = solve_part_one(size, &grid);
-    let part_two = solve_part_two(size, &grid);
+    let part_one = solve_part_one(&contraption);
+    let part_two = solve_part_two(&contraption);
 
     println!("Part one: {}", part_one);
     println!("Part two: {}", part_two);